@@ -1,3 +1,4 @@
+pub mod logging;
 pub mod progress;
 use crate::{
     error::ErrorData as McpError,
@@ -21,6 +22,10 @@ impl<H: ClientHandler> Service<RoleClient> for H {
                 .list_roots(context)
                 .await
                 .map(ClientResult::ListRootsResult),
+            ServerRequest::CreateElicitationRequest(request) => self
+                .create_elicitation(request.params, context)
+                .await
+                .map(ClientResult::CreateElicitationResult),
         }
     }
 
@@ -86,6 +91,19 @@ pub trait ClientHandler: Sized + Send + Sync + 'static {
         std::future::ready(Ok(ListRootsResult::default()))
     }
 
+    /// Ask the user for the input described by `params.requested_schema`.
+    ///
+    /// Clients implementing this should also enable the `elicitation` capability.
+    fn create_elicitation(
+        &self,
+        params: CreateElicitationRequestParam,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<CreateElicitationResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::method_not_found::<
+            CreateElicitationRequestMethod,
+        >()))
+    }
+
     fn on_cancelled(
         &self,
         params: CancelledNotificationParam,
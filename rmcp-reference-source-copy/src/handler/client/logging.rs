@@ -0,0 +1,325 @@
+//! Turn the `notifications/message` of the servers into `tracing` events
+//!
+//! ```rust,no_run
+//! # use rmcp::{ClientHandler, handler::client::logging::{LogStore, LoggingSink}, model::LoggingLevel};
+//! # use rmcp::{RoleClient, transport::IntoTransport};
+//! # async fn example<H, T, E, A>(handler: H, transport: T) -> Result<(), Box<dyn std::error::Error>>
+//! # where
+//! #     H: ClientHandler,
+//! #     T: IntoTransport<RoleClient, E, A>,
+//! #     E: std::error::Error + Send + Sync + 'static,
+//! # {
+//! let logs = LogStore::new(1000);
+//! let client = LoggingSink::new(handler, logs.clone())
+//!     .min_level(LoggingLevel::Warning)
+//!     .connect(transport)
+//!     .await?;
+//! for entry in logs.entries("my-server") {
+//!     println!("{:?} {}", entry.level, entry.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use serde_json::Value;
+
+use super::ClientHandler;
+use crate::{
+    error::ErrorData as McpError,
+    model::*,
+    service::{
+        ClientInitializeError, NotificationContext, Peer, RequestContext, RoleClient,
+        RunningService, ServiceError, serve_client,
+    },
+    transport::IntoTransport,
+};
+
+/// A log message received from a server
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub level: LoggingLevel,
+    pub logger: Option<String>,
+    pub data: Value,
+    pub received_at: SystemTime,
+}
+
+/// The latest log messages of each server, shared by the [`LoggingSink`]s it's given to.
+///
+/// Once a server has sent `capacity` messages, the oldest one is dropped for each new one.
+#[derive(Debug, Clone)]
+pub struct LogStore {
+    capacity: usize,
+    entries: Arc<Mutex<HashMap<String, VecDeque<LogEntry>>>>,
+}
+
+impl LogStore {
+    const DEFAULT_CAPACITY: usize = 256;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, VecDeque<LogEntry>>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn push(&self, server: &str, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        let entries = entries.entry(server.to_owned()).or_default();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The entries of a server, from the oldest to the newest
+    pub fn entries(&self, server: &str) -> Vec<LogEntry> {
+        self.lock()
+            .get(server)
+            .map(|entries| entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The servers which have sent at least one message
+    pub fn servers(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    pub fn clear(&self, server: &str) {
+        self.lock().remove(server);
+    }
+}
+
+impl Default for LogStore {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// A [`ClientHandler`] adapter which emits the log messages of the server as `tracing` events
+/// and keeps them in a [`LogStore`].
+///
+/// The events have the target `rmcp::server_log`, with the server name in the `server` field.
+/// Every other request and notification is handled by the inner handler.
+#[derive(Debug, Clone)]
+pub struct LoggingSink<H> {
+    inner: H,
+    store: LogStore,
+    min_level: Option<LoggingLevel>,
+}
+
+impl<H: ClientHandler> LoggingSink<H> {
+    pub fn new(inner: H, store: LogStore) -> Self {
+        Self {
+            inner,
+            store,
+            min_level: None,
+        }
+    }
+    /// The level requested from the server with `logging/setLevel` on connect
+    pub fn min_level(mut self, level: LoggingLevel) -> Self {
+        self.min_level = Some(level);
+        self
+    }
+    pub fn store(&self) -> &LogStore {
+        &self.store
+    }
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    /// Request the configured level, if any, from a server which supports logging
+    pub async fn apply_min_level(&self, peer: &Peer<RoleClient>) -> Result<(), ServiceError> {
+        let Some(level) = self.min_level else {
+            return Ok(());
+        };
+        let supports_logging = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.logging.is_some());
+        if !supports_logging {
+            return Ok(());
+        }
+        peer.set_level(SetLevelRequestParam { level }).await
+    }
+
+    /// Serve the client, then request the configured level.
+    ///
+    /// Failing to set the level doesn't fail the connection, it's only reported as a warning.
+    pub async fn connect<T, E, A>(
+        self,
+        transport: T,
+    ) -> Result<RunningService<RoleClient, Self>, ClientInitializeError>
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let service = serve_client(self, transport).await?;
+        if let Err(error) = service.service().apply_min_level(service.peer()).await {
+            tracing::warn!(%error, "failed to set the server logging level");
+        }
+        Ok(service)
+    }
+
+    fn emit(&self, server: &str, params: &LoggingMessageNotificationParam) {
+        let LoggingMessageNotificationParam {
+            level,
+            logger,
+            data,
+        } = params;
+        let logger = logger.as_deref().unwrap_or_default();
+        macro_rules! emit {
+            ($macro:ident) => {
+                tracing::$macro!(target: "rmcp::server_log", server, logger, level = ?level, %data)
+            };
+        }
+        match level {
+            LoggingLevel::Debug => emit!(debug),
+            LoggingLevel::Info | LoggingLevel::Notice => emit!(info),
+            LoggingLevel::Warning => emit!(warn),
+            LoggingLevel::Error
+            | LoggingLevel::Critical
+            | LoggingLevel::Alert
+            | LoggingLevel::Emergency => emit!(error),
+        }
+    }
+}
+
+impl<H: ClientHandler> ClientHandler for LoggingSink<H> {
+    async fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        context: NotificationContext<RoleClient>,
+    ) {
+        let server = context
+            .peer
+            .peer_info()
+            .map(|info| info.server_info.name.clone())
+            .unwrap_or_default();
+        self.emit(&server, &params);
+        self.store.push(
+            &server,
+            LogEntry {
+                level: params.level,
+                logger: params.logger.clone(),
+                data: params.data.clone(),
+                received_at: SystemTime::now(),
+            },
+        );
+        self.inner.on_logging_message(params, context).await
+    }
+
+    fn ping(
+        &self,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
+        self.inner.ping(context)
+    }
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<CreateMessageResult, McpError>> + Send + '_ {
+        self.inner.create_message(params, context)
+    }
+    fn list_roots(
+        &self,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<ListRootsResult, McpError>> + Send + '_ {
+        self.inner.list_roots(context)
+    }
+    fn create_elicitation(
+        &self,
+        params: CreateElicitationRequestParam,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<CreateElicitationResult, McpError>> + Send + '_ {
+        self.inner.create_elicitation(params, context)
+    }
+    fn on_cancelled(
+        &self,
+        params: CancelledNotificationParam,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_cancelled(params, context)
+    }
+    fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_progress(params, context)
+    }
+    fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_resource_updated(params, context)
+    }
+    fn on_resource_list_changed(
+        &self,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_resource_list_changed(context)
+    }
+    fn on_tool_list_changed(
+        &self,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_tool_list_changed(context)
+    }
+    fn on_prompt_list_changed(
+        &self,
+        context: NotificationContext<RoleClient>,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.inner.on_prompt_list_changed(context)
+    }
+    fn get_info(&self) -> ClientInfo {
+        self.inner.get_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: LoggingLevel, message: &str) -> LogEntry {
+        LogEntry {
+            level,
+            logger: None,
+            data: Value::from(message),
+            received_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_store_keeps_latest_entries_per_server() {
+        let store = LogStore::new(2);
+        store.push("a", entry(LoggingLevel::Info, "1"));
+        store.push("a", entry(LoggingLevel::Info, "2"));
+        store.push("b", entry(LoggingLevel::Error, "x"));
+        store.push("a", entry(LoggingLevel::Warning, "3"));
+
+        let a = store.entries("a");
+        assert_eq!(a.len(), 2);
+        assert_eq!(a[0].data, "2");
+        assert_eq!(a[1].data, "3");
+        assert_eq!(store.entries("b").len(), 1);
+        assert!(store.entries("c").is_empty());
+
+        store.clear("a");
+        assert_eq!(store.servers(), ["b"]);
+    }
+}
@@ -1,12 +1,17 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use futures::{Stream, StreamExt};
-use tokio::sync::RwLock;
-use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use crate::model::{ProgressNotificationParam, ProgressToken};
-type Dispatcher =
-    Arc<RwLock<HashMap<ProgressToken, tokio::sync::mpsc::Sender<ProgressNotificationParam>>>>;
+// a sync lock and unbounded channels, so that the service loop can dispatch without waiting
+// and without dropping any notification
+type Senders =
+    HashMap<ProgressToken, tokio::sync::mpsc::UnboundedSender<ProgressNotificationParam>>;
+type Dispatcher = Arc<RwLock<Senders>>;
 
 /// A dispatcher for progress notifications.
 #[derive(Debug, Clone, Default)]
@@ -15,19 +20,35 @@ pub struct ProgressDispatcher {
 }
 
 impl ProgressDispatcher {
-    const CHANNEL_SIZE: usize = 16;
     pub fn new() -> Self {
         Self::default()
     }
 
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Senders> {
+        self.dispatcher
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Senders> {
+        self.dispatcher
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     /// Handle a progress notification by sending it to the appropriate subscriber
     pub async fn handle_notification(&self, notification: ProgressNotificationParam) {
-        let token = &notification.progress_token;
-        if let Some(sender) = self.dispatcher.read().await.get(token).cloned() {
-            let send_result = sender.send(notification).await;
-            if let Err(e) = send_result {
-                tracing::warn!("Failed to send progress notification: {e}");
-            }
+        self.try_handle_notification(notification);
+    }
+
+    /// Handle a progress notification without waiting
+    pub(crate) fn try_handle_notification(&self, notification: ProgressNotificationParam) {
+        let senders = self.read();
+        let Some(sender) = senders.get(&notification.progress_token) else {
+            return;
+        };
+        if let Err(e) = sender.send(notification) {
+            tracing::warn!("Failed to send progress notification: {e}");
         }
     }
 
@@ -35,12 +56,9 @@ impl ProgressDispatcher {
     ///
     /// If you drop the returned `ProgressSubscriber`, it will automatically unsubscribe from notifications for that token.
     pub async fn subscribe(&self, progress_token: ProgressToken) -> ProgressSubscriber {
-        let (sender, receiver) = tokio::sync::mpsc::channel(Self::CHANNEL_SIZE);
-        self.dispatcher
-            .write()
-            .await
-            .insert(progress_token.clone(), sender);
-        let receiver = ReceiverStream::new(receiver);
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.write().insert(progress_token.clone(), sender);
+        let receiver = UnboundedReceiverStream::new(receiver);
         ProgressSubscriber {
             progress_token,
             receiver,
@@ -50,19 +68,18 @@ impl ProgressDispatcher {
 
     /// Unsubscribe from progress notifications for a specific token.
     pub async fn unsubscribe(&self, token: &ProgressToken) {
-        self.dispatcher.write().await.remove(token);
+        self.write().remove(token);
     }
 
     /// Clear all dispachter.
     pub async fn clear(&self) {
-        let mut dispacher = self.dispatcher.write().await;
-        dispacher.clear();
+        self.write().clear();
     }
 }
 
 pub struct ProgressSubscriber {
     pub(crate) progress_token: ProgressToken,
-    pub(crate) receiver: ReceiverStream<ProgressNotificationParam>,
+    pub(crate) receiver: UnboundedReceiverStream<ProgressNotificationParam>,
     pub(crate) dispacher: Dispatcher,
 }
 
@@ -89,12 +106,10 @@ impl Stream for ProgressSubscriber {
 
 impl Drop for ProgressSubscriber {
     fn drop(&mut self) {
-        let token = self.progress_token.clone();
         self.receiver.close();
-        let dispatcher = self.dispacher.clone();
-        tokio::spawn(async move {
-            let mut dispacher = dispatcher.write_owned().await;
-            dispacher.remove(&token);
-        });
+        self.dispacher
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.progress_token);
     }
 }
@@ -4,8 +4,17 @@ use crate::{
     service::{NotificationContext, RequestContext, RoleServer, Service, ServiceRole},
 };
 
-mod resource;
+pub mod completion;
+#[cfg(feature = "tracing-layer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing-layer")))]
+pub mod logging;
+pub mod pagination;
+pub mod progress;
+pub mod prompt;
+pub mod resource;
+pub mod roots;
 pub mod router;
+pub mod subscription;
 pub mod tool;
 pub mod wrapper;
 impl<H: ServerHandler> Service<RoleServer> for H {
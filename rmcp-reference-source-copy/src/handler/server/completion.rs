@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use futures::future::{BoxFuture, FutureExt};
+
+pub use super::router::completion::CompletionRouter;
+use crate::{
+    RoleServer,
+    model::{ArgumentInfo, CompleteRequestParam, CompleteResult, CompletionInfo, Reference},
+    service::RequestContext,
+};
+
+/// The maximum number of values in a completion result, as required by the specification
+///
+/// This is a cap, not a page size: `completion/complete` has no cursor, the values past it
+/// can't be fetched. A provider should filter its candidates by the typed value instead.
+pub const MAX_COMPLETION_VALUES: usize = 100;
+
+pub struct CompletionContext<'s, S> {
+    pub request_context: RequestContext<RoleServer>,
+    pub service: &'s S,
+    pub reference: Reference,
+    pub argument: ArgumentInfo,
+}
+
+impl<'s, S> CompletionContext<'s, S> {
+    pub fn new(
+        service: &'s S,
+        CompleteRequestParam { r#ref, argument }: CompleteRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service,
+            reference: r#ref,
+            argument,
+        }
+    }
+}
+
+pub type DynCompletionHandler<S> = dyn for<'s> Fn(CompletionContext<'s, S>) -> BoxFuture<'s, Result<Vec<String>, crate::ErrorData>>
+    + Send
+    + Sync;
+
+/// Produce the candidate values of a prompt argument or a resource template variable
+pub enum CompletionProvider<S> {
+    /// Always the same values, whatever has been typed
+    Values(Arc<[String]>),
+    /// The values starting with what has been typed, ignoring case
+    Prefix(Arc<[String]>),
+    /// Values computed on demand
+    Dyn(Arc<DynCompletionHandler<S>>),
+}
+
+impl<S> Clone for CompletionProvider<S> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Values(values) => Self::Values(values.clone()),
+            Self::Prefix(values) => Self::Prefix(values.clone()),
+            Self::Dyn(handler) => Self::Dyn(handler.clone()),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for CompletionProvider<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Values(values) => f.debug_tuple("Values").field(values).finish(),
+            Self::Prefix(values) => f.debug_tuple("Prefix").field(values).finish(),
+            Self::Dyn(_) => f.debug_tuple("Dyn").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> CompletionProvider<S> {
+    pub fn values(values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::Values(values.into_iter().map(Into::into).collect())
+    }
+    pub fn prefix(values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::Prefix(values.into_iter().map(Into::into).collect())
+    }
+    /// Compute the values with an async function of the argument being completed
+    pub fn from_fn<F, Fut>(f: F) -> Self
+    where
+        F: Fn(ArgumentInfo, RequestContext<RoleServer>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<String>, crate::ErrorData>> + Send + 'static,
+    {
+        Self::new_dyn(move |context: CompletionContext<'_, S>| {
+            f(context.argument, context.request_context).boxed()
+        })
+    }
+    /// Compute the values with access to the service
+    pub fn new_dyn<F>(f: F) -> Self
+    where
+        F: for<'s> Fn(
+                CompletionContext<'s, S>,
+            ) -> BoxFuture<'s, Result<Vec<String>, crate::ErrorData>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::Dyn(Arc::new(f))
+    }
+
+    pub async fn complete(
+        &self,
+        context: CompletionContext<'_, S>,
+    ) -> Result<CompleteResult, crate::ErrorData> {
+        let values = match self {
+            Self::Values(values) => values.to_vec(),
+            Self::Prefix(values) => {
+                let typed = context.argument.value.to_lowercase();
+                values
+                    .iter()
+                    .filter(|value| value.to_lowercase().starts_with(&typed))
+                    .cloned()
+                    .collect()
+            }
+            Self::Dyn(handler) => handler(context).await?,
+        };
+        Ok(completion_result(values))
+    }
+}
+
+/// Build a result from all the candidate values, only the first [`MAX_COMPLETION_VALUES`] are sent
+///
+/// `has_more` tells the client that some values were dropped, so that it keeps completing as
+/// more is typed. It doesn't mean they can be fetched.
+pub fn completion_result(mut values: Vec<String>) -> CompleteResult {
+    let total = values.len();
+    values.truncate(MAX_COMPLETION_VALUES);
+    CompleteResult {
+        completion: CompletionInfo {
+            values,
+            total: Some(u32::try_from(total).unwrap_or(u32::MAX)),
+            has_more: Some(total > MAX_COMPLETION_VALUES),
+        },
+    }
+}
+
+/// The result with no candidate
+pub fn empty_completion_result() -> CompleteResult {
+    completion_result(Vec::new())
+}
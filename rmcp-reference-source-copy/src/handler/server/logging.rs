@@ -0,0 +1,214 @@
+//! Forward `tracing` events to the client as `notifications/message`
+//!
+//! ```rust,no_run
+//! # use rmcp::{ServerHandler, ServiceExt, handler::server::logging::LoggingLayer};
+//! # use tracing_subscriber::layer::SubscriberExt;
+//! # async fn example<S: ServerHandler>(server: S) -> Result<(), Box<dyn std::error::Error>> {
+//! let logging = LoggingLayer::new();
+//! tracing::subscriber::set_global_default(tracing_subscriber::registry().with(logging.clone()))?;
+//! let service = server.serve(rmcp::transport::stdio()).await?;
+//! logging.bind(service.peer().clone());
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::{Arc, RwLock};
+
+use serde_json::Value;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::{
+    model::{
+        JsonObject, LoggingLevel, LoggingMessageNotification, LoggingMessageNotificationParam,
+        ServerNotification,
+    },
+    service::{Peer, RoleServer},
+};
+
+/// A [`Layer`] sending the events to the bound peers.
+///
+/// An event is sent to a peer if its level is at least the one the peer set through
+/// `logging/setLevel`, or the default level if it didn't. The events of this crate are never
+/// sent, since sending a notification emits events itself.
+///
+/// The notifications are queued without waiting, they are dropped when the peer can't keep up.
+#[derive(Debug, Clone)]
+pub struct LoggingLayer {
+    peers: Arc<RwLock<Vec<Peer<RoleServer>>>>,
+    default_level: LoggingLevel,
+}
+
+impl Default for LoggingLayer {
+    fn default() -> Self {
+        Self {
+            peers: Default::default(),
+            default_level: LoggingLevel::Info,
+        }
+    }
+}
+
+impl LoggingLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn for_peer(peer: Peer<RoleServer>) -> Self {
+        let layer = Self::new();
+        layer.bind(peer);
+        layer
+    }
+    /// The level used for peers which haven't set one, [`LoggingLevel::Info`] by default
+    pub fn with_default_level(mut self, level: LoggingLevel) -> Self {
+        self.default_level = level;
+        self
+    }
+    /// Start sending events to a peer, it's unbound once its transport is closed
+    pub fn bind(&self, peer: Peer<RoleServer>) {
+        self.peers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(peer);
+    }
+}
+
+pub fn logging_level_of(level: &Level) -> LoggingLevel {
+    match *level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        Level::DEBUG | Level::TRACE => LoggingLevel::Debug,
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LoggingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return;
+        }
+        let level = logging_level_of(metadata.level());
+        let mut data = None;
+        let mut has_closed_peer = false;
+        {
+            let peers = self
+                .peers
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            for peer in peers.iter() {
+                if peer.is_transport_closed() {
+                    has_closed_peer = true;
+                    continue;
+                }
+                if level < peer.logging_level().unwrap_or(self.default_level) {
+                    continue;
+                }
+                let data = data.get_or_insert_with(|| {
+                    let mut visitor = JsonVisitor(JsonObject::new());
+                    event.record(&mut visitor);
+                    Value::Object(visitor.0)
+                });
+                let notification = LoggingMessageNotification {
+                    method: Default::default(),
+                    params: LoggingMessageNotificationParam {
+                        level,
+                        logger: Some(metadata.target().to_owned()),
+                        data: data.clone(),
+                    },
+                    extensions: Default::default(),
+                };
+                peer.try_send_notification(ServerNotification::LoggingMessageNotification(
+                    notification,
+                ));
+            }
+        }
+        if has_closed_peer {
+            self.peers
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .retain(|peer| !peer.is_transport_closed());
+        }
+    }
+}
+
+struct JsonVisitor(JsonObject);
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: impl Into<Value>) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value)
+    }
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value)
+    }
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value)
+    }
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value)
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value)
+    }
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, value.to_string())
+    }
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{value:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::service::{PeerSinkMessage, test_util};
+
+    #[test]
+    fn test_events_are_forwarded_above_level() {
+        let (peer, mut peer_rx) = test_util::peer::<RoleServer>();
+        let layer = LoggingLayer::for_peer(peer.clone());
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "app", "hidden");
+            tracing::info!(target: "app", answer = 42, "shown");
+            peer.set_logging_level(LoggingLevel::Error);
+            tracing::warn!(target: "app", "hidden");
+            tracing::error!(target: "app", "shown");
+        });
+
+        let mut sent = Vec::new();
+        while let Ok(PeerSinkMessage::Notification { notification, .. }) = peer_rx.try_recv() {
+            let ServerNotification::LoggingMessageNotification(notification) = notification else {
+                panic!("expect a logging message");
+            };
+            sent.push(notification.params);
+        }
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].level, LoggingLevel::Info);
+        assert_eq!(sent[0].logger.as_deref(), Some("app"));
+        assert_eq!(sent[0].data["message"], "shown");
+        assert_eq!(sent[0].data["answer"], 42);
+        assert_eq!(sent[1].level, LoggingLevel::Error);
+    }
+
+    #[test]
+    fn test_router_advertises_logging_when_enabled() {
+        use crate::{ServerHandler, handler::server::router::Router, service::Service};
+
+        struct Server;
+        impl ServerHandler for Server {}
+
+        let router = Router::new(Server);
+        assert!(router.get_info().capabilities.logging.is_none());
+        let router = router.with_logging();
+        assert!(router.get_info().capabilities.logging.is_some());
+    }
+}
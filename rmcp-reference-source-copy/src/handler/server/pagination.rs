@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::model::Cursor;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// HMAC-SHA256 truncated to 128 bits
+const MAC_LEN: usize = 16;
+
+/// Split the lists of a [`Router`](super::router::Router) into pages.
+///
+/// The items are sorted by their name, or uri, and a cursor points after the last item of its
+/// page, so a page doesn't skip or repeat items when the others are added or removed meanwhile.
+///
+/// The cursors are opaque to the clients and signed with HMAC-SHA256, with a random key picked
+/// when the paginator is created: a cursor which was altered, is used for another list, or
+/// comes from another run of the server is rejected with an invalid params error. Set the same
+/// key with [`Paginator::with_key`] to share the cursors between several instances of a server.
+#[derive(Clone)]
+pub struct Paginator {
+    page_size: usize,
+    key: Arc<[u8]>,
+}
+
+impl std::fmt::Debug for Paginator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginator")
+            .field("page_size", &self.page_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Paginator {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PAGE_SIZE)
+    }
+}
+
+impl Paginator {
+    pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+    /// # Panics
+    /// Panics if `page_size` is zero
+    pub fn new(page_size: usize) -> Self {
+        assert!(page_size > 0, "page size must be positive");
+        Self {
+            page_size,
+            key: Arc::new(rand::random::<[u8; 32]>()),
+        }
+    }
+
+    /// Sign the cursors with this key instead of a random one
+    pub fn with_key(mut self, key: impl AsRef<[u8]>) -> Self {
+        self.key = key.as_ref().into();
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    fn hmac(&self, payload: &[u8]) -> HmacSha256 {
+        let mut hmac = HmacSha256::new_from_slice(&self.key).expect("hmac takes keys of any size");
+        hmac.update(payload);
+        hmac
+    }
+
+    fn encode_cursor(&self, list: &str, last_key: &str) -> Cursor {
+        let mut bytes = format!("{list}\n{last_key}").into_bytes();
+        let mac = self.hmac(&bytes).finalize().into_bytes();
+        bytes.extend_from_slice(&mac[..MAC_LEN]);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// The key of the last item before the page the cursor points to
+    fn decode_cursor(&self, list: &str, cursor: &str) -> Result<String, crate::ErrorData> {
+        let invalid = || {
+            crate::ErrorData::invalid_params(
+                "invalid or expired cursor",
+                Some(serde_json::json!({ "cursor": cursor })),
+            )
+        };
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        if bytes.len() < MAC_LEN {
+            return Err(invalid());
+        }
+        let (payload, mac) = bytes.split_at(bytes.len() - MAC_LEN);
+        // in constant time
+        self.hmac(payload)
+            .verify_truncated_left(mac)
+            .map_err(|_| invalid())?;
+        let payload = std::str::from_utf8(payload).map_err(|_| invalid())?;
+        match payload.split_once('\n') {
+            Some((cursor_list, last_key)) if cursor_list == list => Ok(last_key.to_owned()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Take the page of `items` the cursor points to, the first one without cursor.
+    ///
+    /// `list` names the list the cursor is valid for, and `key` gives the unique key the items
+    /// are ordered by.
+    pub fn paginate<T>(
+        &self,
+        list: &str,
+        mut items: Vec<T>,
+        key: impl Fn(&T) -> &str,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<T>, Option<Cursor>), crate::ErrorData> {
+        items.sort_by(|a, b| key(a).cmp(key(b)));
+        let start = match cursor {
+            Some(cursor) => {
+                let last_key = self.decode_cursor(list, cursor)?;
+                items.partition_point(|item| key(item) <= last_key.as_str())
+            }
+            None => 0,
+        };
+        let end = items.len().min(start.saturating_add(self.page_size));
+        let next_cursor = if end < items.len() {
+            Some(self.encode_cursor(list, key(&items[end - 1])))
+        } else {
+            None
+        };
+        items.truncate(end);
+        items.drain(..start);
+        Ok((items, next_cursor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paginate_all(paginator: &Paginator, items: &[&'static str]) -> Vec<Vec<&'static str>> {
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next_cursor) = paginator
+                .paginate("tools", items.to_vec(), |item| *item, cursor.as_deref())
+                .unwrap();
+            pages.push(page);
+            cursor = next_cursor;
+            if cursor.is_none() {
+                return pages;
+            }
+        }
+    }
+
+    #[test]
+    fn test_pages() {
+        let paginator = Paginator::new(2);
+        assert_eq!(
+            paginate_all(&paginator, &["e", "c", "a", "d", "b"]),
+            [vec!["a", "b"], vec!["c", "d"], vec!["e"]]
+        );
+        assert_eq!(paginate_all(&paginator, &["b", "a"]), [vec!["a", "b"]]);
+        assert_eq!(paginate_all(&paginator, &[]), [Vec::<&str>::new()]);
+
+        // the next page starts after the last item, even if it was removed
+        let (_, cursor) = paginator
+            .paginate("tools", vec!["a", "b", "c", "d"], |item| *item, None)
+            .unwrap();
+        let (page, _) = paginator
+            .paginate(
+                "tools",
+                vec!["a", "c", "d"],
+                |item| *item,
+                cursor.as_deref(),
+            )
+            .unwrap();
+        assert_eq!(page, ["c", "d"]);
+    }
+
+    #[test]
+    fn test_invalid_cursors() {
+        let paginator = Paginator::new(1);
+        let (_, cursor) = paginator
+            .paginate("tools", vec!["a", "b"], |item| *item, None)
+            .unwrap();
+        let cursor = cursor.unwrap();
+
+        let mut bytes = URL_SAFE_NO_PAD.decode(&cursor).unwrap();
+        bytes[0] ^= 1;
+        let tampered = URL_SAFE_NO_PAD.encode(bytes);
+        let other_run = Paginator::new(1);
+        let other_key = Paginator::new(1).with_key("other");
+        for (paginator, list, cursor) in [
+            (&paginator, "tools", tampered.as_str()),
+            (&paginator, "tools", "not a cursor"),
+            (&paginator, "tools", ""),
+            (&paginator, "prompts", cursor.as_str()),
+            (&other_run, "tools", cursor.as_str()),
+            (&other_key, "tools", cursor.as_str()),
+        ] {
+            let error = paginator
+                .paginate(list, vec!["a", "b"], |item| *item, Some(cursor))
+                .unwrap_err();
+            assert_eq!(error.code, crate::model::ErrorCode::INVALID_PARAMS);
+        }
+
+        // the instances sharing a key accept the cursors of each other
+        let first = Paginator::new(1).with_key("shared");
+        let second = Paginator::new(1).with_key("shared");
+        let (_, cursor) = first
+            .paginate("tools", vec!["a", "b"], |item| *item, None)
+            .unwrap();
+        let (page, _) = second
+            .paginate("tools", vec!["a", "b"], |item| *item, cursor.as_deref())
+            .unwrap();
+        assert_eq!(page, ["b"]);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use super::tool::{FromToolCallContextPart, ToolCallContext};
+use crate::{
+    RoleServer,
+    model::{ProgressNotification, ProgressNotificationParam, ProgressToken, ServerNotification},
+    service::Peer,
+};
+
+#[derive(Debug)]
+struct ReporterState {
+    min_interval: Duration,
+    last_sent: Option<Instant>,
+    /// The latest update not sent yet
+    pending: Option<ProgressNotificationParam>,
+    flush_scheduled: bool,
+}
+
+#[derive(Debug)]
+struct Reporter {
+    progress_token: ProgressToken,
+    peer: Peer<RoleServer>,
+    ct: CancellationToken,
+    state: Mutex<ReporterState>,
+}
+
+impl Reporter {
+    fn state(&self) -> std::sync::MutexGuard<'_, ReporterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    /// Send the pending update right away
+    fn flush(&self) {
+        let pending = {
+            let mut state = self.state();
+            let pending = state.pending.take();
+            if pending.is_some() {
+                state.last_sent = Some(Instant::now());
+            }
+            pending
+        };
+        if let Some(params) = pending.filter(|_| !self.ct.is_cancelled()) {
+            self.send(params);
+        }
+    }
+    fn send(&self, params: ProgressNotificationParam) {
+        let notification = ProgressNotification {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        };
+        self.peer
+            .try_send_notification(ServerNotification::ProgressNotification(notification));
+    }
+}
+
+impl Drop for Reporter {
+    // the last update isn't left for the scheduled flush, which would run after the tool returns
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Report the progress of a tool call to the caller.
+///
+/// The updates are sent at most once per [`Progress::DEFAULT_MIN_INTERVAL`] by default, the ones
+/// coming faster are coalesced so that only the latest is sent. A coalesced update is sent at
+/// the latest when the reporter and its clones are dropped, or with [`Progress::flush`].
+/// Reporting does nothing when the caller didn't send a progress token, and stops once the
+/// request is cancelled.
+///
+/// It's taken from the `progressToken` of the request meta, so it must be extracted before
+/// [`Meta`](crate::model::Meta) in the parameters of a tool.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    reporter: Option<Arc<Reporter>>,
+}
+
+impl Progress {
+    pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new(
+        progress_token: ProgressToken,
+        peer: Peer<RoleServer>,
+        ct: CancellationToken,
+    ) -> Self {
+        Self {
+            reporter: Some(Arc::new(Reporter {
+                progress_token,
+                peer,
+                ct,
+                state: Mutex::new(ReporterState {
+                    min_interval: Self::DEFAULT_MIN_INTERVAL,
+                    last_sent: None,
+                    pending: None,
+                    flush_scheduled: false,
+                }),
+            })),
+        }
+    }
+
+    /// A reporter sending nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// The minimum time between two updates, shared with the clones of this reporter
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        if let Some(reporter) = &self.reporter {
+            reporter.state().min_interval = min_interval;
+        }
+        self
+    }
+
+    /// Whether the caller asked for progress updates
+    pub fn is_enabled(&self) -> bool {
+        self.reporter.is_some()
+    }
+
+    /// Send the coalesced update now, if any
+    pub fn flush(&self) {
+        if let Some(reporter) = &self.reporter {
+            reporter.flush();
+        }
+    }
+
+    pub fn report(&self, progress: u32, total: Option<u32>, message: Option<String>) {
+        let Some(reporter) = &self.reporter else {
+            return;
+        };
+        if reporter.ct.is_cancelled() {
+            return;
+        }
+        let params = ProgressNotificationParam {
+            progress_token: reporter.progress_token.clone(),
+            progress,
+            total,
+            message,
+        };
+        let now = Instant::now();
+        let mut state = reporter.state();
+        let min_interval = state.min_interval;
+        let next_allowed = state.last_sent.map(|last_sent| last_sent + min_interval);
+        match next_allowed {
+            Some(next_allowed) if next_allowed > now => {
+                state.pending = Some(params);
+                if state.flush_scheduled {
+                    return;
+                }
+                state.flush_scheduled = true;
+                // a dropped reporter flushes itself
+                let weak_reporter = Arc::downgrade(reporter);
+                let ct = reporter.ct.clone();
+                tokio::spawn(async move {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(next_allowed) => {}
+                        _ = ct.cancelled() => return,
+                    }
+                    if let Some(reporter) = weak_reporter.upgrade() {
+                        reporter.state().flush_scheduled = false;
+                        reporter.flush();
+                    }
+                });
+            }
+            _ => {
+                state.last_sent = Some(now);
+                state.pending = None;
+                drop(state);
+                reporter.send(params);
+            }
+        }
+    }
+}
+
+impl<S> FromToolCallContextPart<S> for Progress {
+    fn from_tool_call_context_part(
+        context: &mut ToolCallContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        let request_context = &context.request_context;
+        Ok(match request_context.meta.get_progress_token() {
+            Some(progress_token) => Self::new(
+                progress_token,
+                request_context.peer.clone(),
+                request_context.ct.clone(),
+            ),
+            None => Self::disabled(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::NumberOrString,
+        service::{PeerSinkMessage, test_util},
+    };
+
+    #[tokio::test]
+    async fn test_updates_are_coalesced() {
+        tokio::time::pause();
+        let (peer, mut peer_rx) = test_util::peer::<RoleServer>();
+        let ct = CancellationToken::new();
+        let progress = Progress::new(
+            ProgressToken(NumberOrString::Number(1)),
+            peer.clone(),
+            ct.clone(),
+        )
+        .with_min_interval(Duration::from_millis(50));
+        let mut received = || {
+            let mut progress = Vec::new();
+            while let Ok(PeerSinkMessage::Notification { notification, .. }) = peer_rx.try_recv() {
+                let ServerNotification::ProgressNotification(notification) = notification else {
+                    panic!("expect a progress notification");
+                };
+                progress.push(notification.params.progress);
+            }
+            progress
+        };
+
+        progress.report(1, Some(10), None);
+        progress.report(2, Some(10), None);
+        progress.report(3, Some(10), Some("almost".into()));
+        assert_eq!(received(), [1]);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(received(), [3]);
+
+        // the last update is sent when the tool is done with the reporter
+        progress.report(4, Some(10), None);
+        progress.report(5, Some(10), None);
+        assert_eq!(received(), [4]);
+        let clone = progress.clone();
+        drop(progress);
+        assert!(received().is_empty());
+        drop(clone);
+        assert_eq!(received(), [5]);
+
+        let progress = Progress::new(ProgressToken(NumberOrString::Number(2)), peer, ct.clone());
+        ct.cancel();
+        progress.report(6, Some(10), None);
+        assert!(received().is_empty());
+
+        Progress::disabled().report(1, None, None);
+    }
+}
@@ -0,0 +1,392 @@
+use std::borrow::Cow;
+
+use futures::future::{BoxFuture, FutureExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio_util::sync::CancellationToken;
+
+pub use super::router::prompt::{PromptRoute, PromptRouter};
+use super::tool::{
+    AsyncAdapter, AsyncMethodAdapter, Extension, RequestId, SyncAdapter, SyncMethodAdapter,
+    schema_for_type,
+};
+use crate::{
+    RoleServer,
+    model::{GetPromptRequestParam, GetPromptResult, JsonObject, PromptArgument, PromptMessage},
+    service::RequestContext,
+};
+
+/// Derive the list of [`PromptArgument`]s from the JSON schema of a type.
+///
+/// Every top-level property becomes an argument, its `description` is taken from the
+/// schema and it is marked as required if it appears in the schema's `required` list.
+pub fn arguments_from_schema<T: JsonSchema>() -> Option<Vec<PromptArgument>> {
+    let schema = schema_for_type::<T>();
+    let properties = schema.get("properties").and_then(|p| p.as_object())?;
+    let required = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let arguments = properties
+        .iter()
+        .map(|(name, property)| PromptArgument {
+            name: name.clone(),
+            description: property
+                .get("description")
+                .and_then(|d| d.as_str())
+                .map(ToOwned::to_owned),
+            required: Some(required.contains(&name.as_str())),
+        })
+        .collect::<Vec<_>>();
+    if arguments.is_empty() {
+        None
+    } else {
+        Some(arguments)
+    }
+}
+
+pub struct PromptContext<'s, S> {
+    pub request_context: RequestContext<RoleServer>,
+    pub service: &'s S,
+    pub name: String,
+    pub arguments: Option<JsonObject>,
+}
+
+impl<'s, S> PromptContext<'s, S> {
+    pub fn new(
+        service: &'s S,
+        GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service,
+            name,
+            arguments,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn request_context(&self) -> &RequestContext<RoleServer> {
+        &self.request_context
+    }
+    pub fn invoke<H, A>(self, h: H) -> BoxFuture<'s, Result<GetPromptResult, crate::ErrorData>>
+    where
+        H: GetPromptHandler<S, A>,
+    {
+        h.handle(self)
+    }
+}
+
+pub trait FromPromptContextPart<S>: Sized {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData>;
+}
+
+pub trait IntoGetPromptResult {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::ErrorData>;
+}
+
+impl IntoGetPromptResult for GetPromptResult {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::ErrorData> {
+        Ok(self)
+    }
+}
+
+impl IntoGetPromptResult for Vec<PromptMessage> {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::ErrorData> {
+        Ok(GetPromptResult {
+            description: None,
+            messages: self,
+        })
+    }
+}
+
+impl<T: IntoGetPromptResult> IntoGetPromptResult for Result<T, crate::ErrorData> {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::ErrorData> {
+        self.and_then(IntoGetPromptResult::into_get_prompt_result)
+    }
+}
+
+pub trait GetPromptHandler<S, A> {
+    fn handle(
+        self,
+        context: PromptContext<'_, S>,
+    ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>>;
+}
+
+pub type DynGetPromptHandler<S> = dyn for<'s> Fn(PromptContext<'s, S>) -> BoxFuture<'s, Result<GetPromptResult, crate::ErrorData>>
+    + Send
+    + Sync;
+
+/// Prompt arguments extractor
+///
+/// The arguments of the `prompts/get` request are deserialized into `P`, and the
+/// [`PromptArgument`] list of the prompt can be derived from the schema of `P`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Arguments<P>(pub P);
+
+impl<P: JsonSchema> JsonSchema for Arguments<P> {
+    fn schema_name() -> Cow<'static, str> {
+        P::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        P::json_schema(generator)
+    }
+}
+
+impl<S, P> FromPromptContextPart<S> for Arguments<P>
+where
+    P: DeserializeOwned,
+{
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        let arguments = context.arguments.take().unwrap_or_default();
+        let value: P =
+            serde_json::from_value(serde_json::Value::Object(arguments)).map_err(|e| {
+                crate::ErrorData::invalid_params(
+                    format!("failed to deserialize arguments: {error}", error = e),
+                    None,
+                )
+            })?;
+        Ok(Arguments(value))
+    }
+}
+
+impl<S> FromPromptContextPart<S> for JsonObject {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        let object = context.arguments.take().unwrap_or_default();
+        Ok(object)
+    }
+}
+
+impl<S> FromPromptContextPart<S> for CancellationToken {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.ct.clone())
+    }
+}
+
+pub struct PromptName(pub String);
+
+impl<S> FromPromptContextPart<S> for PromptName {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(Self(context.name.clone()))
+    }
+}
+
+impl<S> FromPromptContextPart<S> for crate::model::Extensions {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.extensions.clone())
+    }
+}
+
+impl<S, T> FromPromptContextPart<S> for Extension<T>
+where
+    T: Send + Sync + 'static + Clone,
+{
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        let extension = context
+            .request_context
+            .extensions
+            .get::<T>()
+            .cloned()
+            .ok_or_else(|| {
+                crate::ErrorData::invalid_params(
+                    format!("missing extension {}", std::any::type_name::<T>()),
+                    None,
+                )
+            })?;
+        Ok(Extension(extension))
+    }
+}
+
+impl<S> FromPromptContextPart<S> for crate::Peer<RoleServer> {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.peer.clone())
+    }
+}
+
+impl<S> FromPromptContextPart<S> for crate::model::Meta {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        let mut meta = crate::model::Meta::default();
+        std::mem::swap(&mut meta, &mut context.request_context.meta);
+        Ok(meta)
+    }
+}
+
+impl<S> FromPromptContextPart<S> for RequestId {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(RequestId(context.request_context.id.clone()))
+    }
+}
+
+impl<S> FromPromptContextPart<S> for RequestContext<RoleServer> {
+    fn from_prompt_context_part(context: &mut PromptContext<S>) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.clone())
+    }
+}
+
+macro_rules! impl_for {
+    ($($T: ident)*) => {
+        impl_for!([] [$($T)*]);
+    };
+    // finished
+    ([$($Tn: ident)*] []) => {
+        impl_for!(@impl $($Tn)*);
+    };
+    ([$($Tn: ident)*] [$Tn_1: ident $($Rest: ident)*]) => {
+        impl_for!(@impl $($Tn)*);
+        impl_for!([$($Tn)* $Tn_1] [$($Rest)*]);
+    };
+    (@impl $($Tn: ident)*) => {
+        impl<$($Tn,)* S, F, R> GetPromptHandler<S, AsyncMethodAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromPromptContextPart<S>,
+            )*
+            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R>,
+            R: IntoGetPromptResult + Send + 'static,
+            S: Send + Sync + 'static,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn handle(
+                self,
+                mut context: PromptContext<'_, S>,
+            ) -> BoxFuture<'_, Result<GetPromptResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_prompt_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                let service = context.service;
+                let fut = self(service, $($Tn,)*);
+                async move {
+                    let result = fut.await;
+                    result.into_get_prompt_result()
+                }.boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, Fut, R> GetPromptHandler<S, AsyncAdapter<($($Tn,)*), Fut, R>> for F
+        where
+            $(
+                $Tn: FromPromptContextPart<S>,
+            )*
+            F: FnOnce($($Tn,)*) -> Fut + Send,
+            Fut: Future<Output = R> + Send + 'static,
+            R: IntoGetPromptResult + Send + 'static,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn handle(
+                self,
+                mut context: PromptContext<S>,
+            ) -> BoxFuture<'static, Result<GetPromptResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_prompt_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                let fut = self($($Tn,)*);
+                async move {
+                    let result = fut.await;
+                    result.into_get_prompt_result()
+                }.boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, R> GetPromptHandler<S, SyncMethodAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromPromptContextPart<S>,
+            )*
+            F: FnOnce(&S, $($Tn,)*) -> R + Send,
+            R: IntoGetPromptResult + Send,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn handle(
+                self,
+                mut context: PromptContext<S>,
+            ) -> BoxFuture<'static, Result<GetPromptResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_prompt_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                std::future::ready(self(context.service, $($Tn,)*).into_get_prompt_result()).boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, R> GetPromptHandler<S, SyncAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromPromptContextPart<S>,
+            )*
+            F: FnOnce($($Tn,)*) -> R + Send,
+            R: IntoGetPromptResult + Send,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn handle(
+                self,
+                mut context: PromptContext<S>,
+            ) -> BoxFuture<'static, Result<GetPromptResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_prompt_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                std::future::ready(self($($Tn,)*).into_get_prompt_result()).boxed()
+            }
+        }
+    };
+}
+impl_for!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct ReviewArguments {
+        /// The code to review
+        code: String,
+        /// Optional focus area
+        focus: Option<String>,
+    }
+
+    #[test]
+    fn test_arguments_from_schema() {
+        let mut arguments =
+            arguments_from_schema::<ReviewArguments>().expect("arguments should be derived");
+        arguments.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            arguments,
+            vec![
+                PromptArgument {
+                    name: "code".into(),
+                    description: Some("The code to review".into()),
+                    required: Some(true),
+                },
+                PromptArgument {
+                    name: "focus".into(),
+                    description: Some("Optional focus area".into()),
+                    required: Some(false),
+                },
+            ]
+        );
+        assert_eq!(arguments_from_schema::<crate::model::EmptyObject>(), None);
+    }
+}
@@ -1 +1,472 @@
+use std::collections::HashMap;
 
+use futures::future::{BoxFuture, FutureExt};
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeOwned, IntoDeserializer, Visitor, value::MapDeserializer},
+};
+use tokio_util::sync::CancellationToken;
+
+pub use super::router::resource::{
+    InvalidResourceTemplate, ResourceRoute, ResourceRouter, ResourceTemplateRoute,
+};
+use super::tool::{
+    AsyncAdapter, AsyncMethodAdapter, Extension, RequestId, SyncAdapter, SyncMethodAdapter,
+};
+use crate::{
+    RoleServer,
+    model::{ReadResourceRequestParam, ReadResourceResult, ResourceContents},
+    service::RequestContext,
+};
+
+mod uri_template;
+pub use uri_template::*;
+#[cfg(feature = "fs-resources")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs-resources")))]
+mod filesystem;
+#[cfg(feature = "fs-resources")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs-resources")))]
+pub use filesystem::*;
+
+/// Deserialize the variables extracted from a uri template into a type
+///
+/// The values are strings in the uri, they will be parsed when the target field is a
+/// number or a boolean.
+pub fn parse_template_variables<T: DeserializeOwned>(
+    variables: &HashMap<String, String>,
+) -> Result<T, crate::ErrorData> {
+    let deserializer = MapDeserializer::<_, serde::de::value::Error>::new(
+        variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), TemplateValue(value.as_str()))),
+    );
+    T::deserialize(deserializer).map_err(|e| {
+        crate::ErrorData::invalid_params(
+            format!("failed to deserialize uri parameters: {error}", error = e),
+            None,
+        )
+    })
+}
+
+struct TemplateValue<'a>(&'a str);
+
+impl<'de> IntoDeserializer<'de, serde::de::value::Error> for TemplateValue<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method: ident => $visit: ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let value = self.0.parse().map_err(serde::de::Error::custom)?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for TemplateValue<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any i128 u128
+    }
+}
+
+pub struct ResourceContext<'s, S> {
+    pub request_context: RequestContext<RoleServer>,
+    pub service: &'s S,
+    pub uri: String,
+    /// Variables extracted from the uri when it matched a resource template
+    pub variables: HashMap<String, String>,
+}
+
+impl<'s, S> ResourceContext<'s, S> {
+    pub fn new(
+        service: &'s S,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service,
+            uri,
+            variables: HashMap::new(),
+        }
+    }
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+    pub fn request_context(&self) -> &RequestContext<RoleServer> {
+        &self.request_context
+    }
+    pub fn invoke<H, A>(self, h: H) -> BoxFuture<'s, Result<ReadResourceResult, crate::ErrorData>>
+    where
+        H: ReadResourceHandler<S, A>,
+    {
+        h.read(self)
+    }
+}
+
+pub trait FromResourceContextPart<S>: Sized {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData>;
+}
+
+pub trait IntoReadResourceResult {
+    fn into_read_resource_result(self) -> Result<ReadResourceResult, crate::ErrorData>;
+}
+
+impl IntoReadResourceResult for ReadResourceResult {
+    fn into_read_resource_result(self) -> Result<ReadResourceResult, crate::ErrorData> {
+        Ok(self)
+    }
+}
+
+impl IntoReadResourceResult for Vec<ResourceContents> {
+    fn into_read_resource_result(self) -> Result<ReadResourceResult, crate::ErrorData> {
+        Ok(ReadResourceResult { contents: self })
+    }
+}
+
+impl IntoReadResourceResult for ResourceContents {
+    fn into_read_resource_result(self) -> Result<ReadResourceResult, crate::ErrorData> {
+        Ok(ReadResourceResult {
+            contents: vec![self],
+        })
+    }
+}
+
+impl<T: IntoReadResourceResult> IntoReadResourceResult for Result<T, crate::ErrorData> {
+    fn into_read_resource_result(self) -> Result<ReadResourceResult, crate::ErrorData> {
+        self.and_then(IntoReadResourceResult::into_read_resource_result)
+    }
+}
+
+pub trait ReadResourceHandler<S, A> {
+    fn read(
+        self,
+        context: ResourceContext<'_, S>,
+    ) -> BoxFuture<'_, Result<ReadResourceResult, crate::ErrorData>>;
+}
+
+pub type DynReadResourceHandler<S> = dyn for<'s> Fn(
+        ResourceContext<'s, S>,
+    ) -> BoxFuture<'s, Result<ReadResourceResult, crate::ErrorData>>
+    + Send
+    + Sync;
+
+/// Uri template parameters extractor
+///
+/// The variables extracted from the requested uri are deserialized into `P`,
+/// see [`parse_template_variables`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UriParameters<P>(pub P);
+
+impl<S, P> FromResourceContextPart<S> for UriParameters<P>
+where
+    P: DeserializeOwned,
+{
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        parse_template_variables(&context.variables).map(UriParameters)
+    }
+}
+
+/// The uri of the resource being read
+pub struct ResourceUri(pub String);
+
+impl<S> FromResourceContextPart<S> for ResourceUri {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(Self(context.uri.clone()))
+    }
+}
+
+impl<S> FromResourceContextPart<S> for HashMap<String, String> {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(std::mem::take(&mut context.variables))
+    }
+}
+
+impl<S> FromResourceContextPart<S> for CancellationToken {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.ct.clone())
+    }
+}
+
+impl<S> FromResourceContextPart<S> for crate::model::Extensions {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.extensions.clone())
+    }
+}
+
+impl<S, T> FromResourceContextPart<S> for Extension<T>
+where
+    T: Send + Sync + 'static + Clone,
+{
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        let extension = context
+            .request_context
+            .extensions
+            .get::<T>()
+            .cloned()
+            .ok_or_else(|| {
+                crate::ErrorData::invalid_params(
+                    format!("missing extension {}", std::any::type_name::<T>()),
+                    None,
+                )
+            })?;
+        Ok(Extension(extension))
+    }
+}
+
+impl<S> FromResourceContextPart<S> for crate::Peer<RoleServer> {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.peer.clone())
+    }
+}
+
+impl<S> FromResourceContextPart<S> for crate::model::Meta {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        let mut meta = crate::model::Meta::default();
+        std::mem::swap(&mut meta, &mut context.request_context.meta);
+        Ok(meta)
+    }
+}
+
+impl<S> FromResourceContextPart<S> for RequestId {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(RequestId(context.request_context.id.clone()))
+    }
+}
+
+impl<S> FromResourceContextPart<S> for RequestContext<RoleServer> {
+    fn from_resource_context_part(
+        context: &mut ResourceContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        Ok(context.request_context.clone())
+    }
+}
+
+macro_rules! impl_for {
+    ($($T: ident)*) => {
+        impl_for!([] [$($T)*]);
+    };
+    // finished
+    ([$($Tn: ident)*] []) => {
+        impl_for!(@impl $($Tn)*);
+    };
+    ([$($Tn: ident)*] [$Tn_1: ident $($Rest: ident)*]) => {
+        impl_for!(@impl $($Tn)*);
+        impl_for!([$($Tn)* $Tn_1] [$($Rest)*]);
+    };
+    (@impl $($Tn: ident)*) => {
+        impl<$($Tn,)* S, F, R> ReadResourceHandler<S, AsyncMethodAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromResourceContextPart<S>,
+            )*
+            F: FnOnce(&S, $($Tn,)*) -> BoxFuture<'_, R>,
+            R: IntoReadResourceResult + Send + 'static,
+            S: Send + Sync + 'static,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn read(
+                self,
+                mut context: ResourceContext<'_, S>,
+            ) -> BoxFuture<'_, Result<ReadResourceResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_resource_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                let service = context.service;
+                let fut = self(service, $($Tn,)*);
+                async move {
+                    let result = fut.await;
+                    result.into_read_resource_result()
+                }.boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, Fut, R> ReadResourceHandler<S, AsyncAdapter<($($Tn,)*), Fut, R>> for F
+        where
+            $(
+                $Tn: FromResourceContextPart<S>,
+            )*
+            F: FnOnce($($Tn,)*) -> Fut + Send,
+            Fut: Future<Output = R> + Send + 'static,
+            R: IntoReadResourceResult + Send + 'static,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn read(
+                self,
+                mut context: ResourceContext<S>,
+            ) -> BoxFuture<'static, Result<ReadResourceResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_resource_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                let fut = self($($Tn,)*);
+                async move {
+                    let result = fut.await;
+                    result.into_read_resource_result()
+                }.boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, R> ReadResourceHandler<S, SyncMethodAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromResourceContextPart<S>,
+            )*
+            F: FnOnce(&S, $($Tn,)*) -> R + Send,
+            R: IntoReadResourceResult + Send,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn read(
+                self,
+                mut context: ResourceContext<S>,
+            ) -> BoxFuture<'static, Result<ReadResourceResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_resource_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                std::future::ready(self(context.service, $($Tn,)*).into_read_resource_result()).boxed()
+            }
+        }
+
+        impl<$($Tn,)* S, F, R> ReadResourceHandler<S, SyncAdapter<($($Tn,)*), R>> for F
+        where
+            $(
+                $Tn: FromResourceContextPart<S>,
+            )*
+            F: FnOnce($($Tn,)*) -> R + Send,
+            R: IntoReadResourceResult + Send,
+            S: Send + Sync,
+        {
+            #[allow(unused_variables, non_snake_case, unused_mut)]
+            fn read(
+                self,
+                mut context: ResourceContext<S>,
+            ) -> BoxFuture<'static, Result<ReadResourceResult, crate::ErrorData>> {
+                $(
+                    let result = $Tn::from_resource_context_part(&mut context);
+                    let $Tn = match result {
+                        Ok(value) => value,
+                        Err(e) => return std::future::ready(Err(e)).boxed(),
+                    };
+                )*
+                std::future::ready(self($($Tn,)*).into_read_resource_result()).boxed()
+            }
+        }
+    };
+}
+impl_for!(T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct IssueParameters {
+        owner: String,
+        number: u32,
+        closed: Option<bool>,
+    }
+
+    #[test]
+    fn test_parse_template_variables() {
+        let variables = HashMap::from([
+            ("owner".to_owned(), "keipes".to_owned()),
+            ("number".to_owned(), "42".to_owned()),
+        ]);
+        let parameters: IssueParameters = parse_template_variables(&variables).unwrap();
+        assert_eq!(
+            parameters,
+            IssueParameters {
+                owner: "keipes".into(),
+                number: 42,
+                closed: None,
+            }
+        );
+        let variables = HashMap::from([
+            ("owner".to_owned(), "keipes".to_owned()),
+            ("number".to_owned(), "forty-two".to_owned()),
+        ]);
+        assert!(parse_template_variables::<IssueParameters>(&variables).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+//! Expose directories as `file://` resources
+//!
+//! ```rust,no_run
+//! # use rmcp::handler::server::{resource::FileSystemResources, subscription::SubscriptionManager};
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let resources = FileSystemResources::new().with_root("./docs")?;
+//! let subscriptions = SubscriptionManager::new();
+//! // keep the watcher alive as long as the changes should be notified
+//! let watcher = resources.watch(subscriptions.clone())?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use base64::engine::{Engine, general_purpose::STANDARD as BASE64_STANDARD};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind};
+
+use crate::{
+    RoleServer,
+    handler::server::subscription::SubscriptionManager,
+    model::{AnnotateAble, RawResource, ReadResourceResult, Resource, ResourceContents},
+    service::Peer,
+};
+
+/// How long the watcher waits for more changes before notifying them together
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+struct FileSystemRoot {
+    /// Canonical path of the directory
+    path: PathBuf,
+}
+
+/// Serve the files under some directories, the roots, as resources.
+///
+/// A file is identified by its `file://` url. The paths are resolved
+/// before being read, so a uri with `..` or a symbolic link leading outside of the roots is
+/// rejected, and such links are not listed.
+///
+/// Valid UTF-8 files are served as text, the others as base64 blobs, with the MIME type
+/// guessed from the file extension.
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemResources {
+    roots: Vec<FileSystemRoot>,
+}
+
+impl FileSystemResources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory, it must exist
+    pub fn with_root(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        if !path.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", path.display()),
+            ));
+        }
+        self.roots.push(FileSystemRoot { path });
+        Ok(self)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = &Path> {
+        self.roots.iter().map(|root| root.path.as_path())
+    }
+
+    /// The `file://` uri of a path, `None` if it isn't absolute
+    pub fn uri_of(path: &Path) -> Option<String> {
+        url::Url::from_file_path(path).ok().map(String::from)
+    }
+
+    fn is_within_roots(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(&root.path))
+    }
+
+    /// Resolve a uri to the path of a file under the roots
+    pub async fn resolve(&self, uri: &str) -> Result<PathBuf, crate::ErrorData> {
+        let not_found = || {
+            crate::ErrorData::resource_not_found(
+                "resource not found",
+                Some(serde_json::json!({ "uri": uri })),
+            )
+        };
+        // percent-decoded, and only a local host is accepted
+        let path = url::Url::parse(uri)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(not_found)?;
+        // resolves `..` and symbolic links, so that the check below can't be escaped
+        let path = tokio::fs::canonicalize(path)
+            .await
+            .map_err(|_| not_found())?;
+        if !self.is_within_roots(&path) || !path.is_file() {
+            return Err(not_found());
+        }
+        Ok(path)
+    }
+
+    /// All the files under the roots
+    pub async fn list_resources(&self) -> std::io::Result<Vec<Resource>> {
+        let mut resources = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending: Vec<PathBuf> = self.roots().map(Path::to_path_buf).collect();
+        while let Some(dir) = pending.pop() {
+            if !visited.insert(dir.clone()) {
+                continue;
+            }
+            // an unreadable directory doesn't hide the rest of the files
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(error) => {
+                    tracing::warn!(?dir, %error, "skip unreadable directory");
+                    continue;
+                }
+            };
+            loop {
+                let entry = match entries.next_entry().await {
+                    Ok(Some(entry)) => entry,
+                    Ok(None) => break,
+                    Err(error) => {
+                        tracing::warn!(?dir, %error, "skip the rest of unreadable directory");
+                        break;
+                    }
+                };
+                let path = entry.path();
+                let Ok(resolved) = tokio::fs::canonicalize(&path).await else {
+                    continue;
+                };
+                if !self.is_within_roots(&resolved) {
+                    continue;
+                }
+                let Ok(metadata) = tokio::fs::metadata(&resolved).await else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(resolved);
+                } else if metadata.is_file() {
+                    resources.extend(Self::resource(&path, metadata.len()));
+                }
+            }
+        }
+        resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+        Ok(resources)
+    }
+
+    fn resource(path: &Path, len: u64) -> Option<Resource> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut resource = RawResource::new(Self::uri_of(path)?, name);
+        resource.mime_type = mime_guess::from_path(path)
+            .first()
+            .map(|mime| mime.essence_str().to_owned());
+        resource.size = u32::try_from(len).ok();
+        Some(resource.no_annotation())
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, crate::ErrorData> {
+        let path = self.resolve(uri).await?;
+        let bytes = tokio::fs::read(&path).await.map_err(|e| {
+            crate::ErrorData::internal_error(format!("failed to read {uri}: {e}"), None)
+        })?;
+        let mime = mime_guess::from_path(&path).first();
+        let is_binary_type = mime.as_ref().is_some_and(|mime| {
+            matches!(
+                mime.type_(),
+                mime_guess::mime::IMAGE | mime_guess::mime::AUDIO | mime_guess::mime::VIDEO
+            )
+        });
+        let mime_type = mime.map(|mime| mime.essence_str().to_owned());
+        let contents = match String::from_utf8(bytes) {
+            Ok(text) if !is_binary_type => ResourceContents::TextResourceContents {
+                uri: uri.to_owned(),
+                mime_type,
+                text,
+            },
+            Ok(text) => ResourceContents::BlobResourceContents {
+                uri: uri.to_owned(),
+                mime_type,
+                blob: BASE64_STANDARD.encode(text),
+            },
+            Err(e) => ResourceContents::BlobResourceContents {
+                uri: uri.to_owned(),
+                mime_type,
+                blob: BASE64_STANDARD.encode(e.into_bytes()),
+            },
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    /// Watch the roots for changes.
+    ///
+    /// A modified file is notified to its subscribers with `notifications/resources/updated`,
+    /// a created, removed or renamed file is notified to the peers bound to the watcher with
+    /// `notifications/resources/list_changed`. The watching stops when the watcher is dropped.
+    pub fn watch(&self, subscriptions: SubscriptionManager) -> notify::Result<ResourceWatcher> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        for root in self.roots() {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+        }
+        let peers: Arc<RwLock<Vec<Peer<RoleServer>>>> = Default::default();
+        let task_peers = peers.clone();
+        let resources = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                // an editor saving a file emits a burst of events, notify them once
+                let mut changes = WatchedChanges::default();
+                changes.add(&resources, event);
+                let debounce = tokio::time::sleep(WATCH_DEBOUNCE);
+                tokio::pin!(debounce);
+                loop {
+                    tokio::select! {
+                        event = rx.recv() => match event {
+                            Some(event) => changes.add(&resources, event),
+                            None => break,
+                        },
+                        _ = &mut debounce => break,
+                    }
+                }
+                if changes.list_changed {
+                    let peers = {
+                        let mut peers = task_peers
+                            .write()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                        peers.retain(|peer: &Peer<RoleServer>| !peer.is_transport_closed());
+                        peers.clone()
+                    };
+                    for peer in peers {
+                        if let Err(error) = peer.notify_resource_list_changed().await {
+                            tracing::warn!(%error, "failed to notify resource list change");
+                        }
+                    }
+                }
+                for uri in changes.updated {
+                    subscriptions.notify_updated(uri).await;
+                }
+            }
+        });
+        Ok(ResourceWatcher {
+            _watcher: watcher,
+            peers,
+        })
+    }
+}
+
+/// The changes seen by the watcher during a debounce window
+#[derive(Debug, Default)]
+struct WatchedChanges {
+    list_changed: bool,
+    /// The uris of the modified files
+    updated: BTreeSet<String>,
+}
+
+impl WatchedChanges {
+    fn add(&mut self, resources: &FileSystemResources, event: notify::Result<notify::Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => {
+                tracing::warn!(%error, "filesystem watch error");
+                return;
+            }
+        };
+        let mut paths = event
+            .paths
+            .iter()
+            .filter(|path| resources.is_within_roots(path))
+            .peekable();
+        match event.kind {
+            EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_)) => {
+                self.list_changed |= paths.peek().is_some();
+            }
+            EventKind::Modify(_) => {
+                self.updated
+                    .extend(paths.filter_map(|path| FileSystemResources::uri_of(path)));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Watch the roots of [`FileSystemResources`], see [`FileSystemResources::watch`]
+#[derive(Debug)]
+pub struct ResourceWatcher {
+    _watcher: RecommendedWatcher,
+    peers: Arc<RwLock<Vec<Peer<RoleServer>>>>,
+}
+
+impl ResourceWatcher {
+    /// Notify a peer of the changes of the resource list, until its transport is closed
+    pub fn bind(&self, peer: Peer<RoleServer>) {
+        self.peers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rmcp-fs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_list_and_read() {
+        let root = temp_dir("root");
+        let outside = temp_dir("outside");
+        std::fs::create_dir(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/readme.md"), "# hello").unwrap();
+        std::fs::write(root.join("image.png"), [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        std::fs::write(root.join("with space.txt"), "spaced").unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+
+        let resources = FileSystemResources::new().with_root(&root).unwrap();
+        let root = std::fs::canonicalize(&root).unwrap();
+        let listed = resources.list_resources().await.unwrap();
+        let uris: Vec<_> = listed.iter().map(|r| r.uri.clone()).collect();
+        assert_eq!(
+            uris,
+            [
+                FileSystemResources::uri_of(&root.join("image.png")).unwrap(),
+                FileSystemResources::uri_of(&root.join("sub/readme.md")).unwrap(),
+                FileSystemResources::uri_of(&root.join("with space.txt")).unwrap(),
+            ]
+        );
+        assert!(uris[2].ends_with("/with%20space.txt"));
+        assert_eq!(listed[1].mime_type.as_deref(), Some("text/markdown"));
+
+        let readme = resources.read_resource(&uris[1]).await.unwrap();
+        assert!(matches!(
+            &readme.contents[0],
+            ResourceContents::TextResourceContents { text, .. } if text == "# hello"
+        ));
+        let image = resources.read_resource(&uris[0]).await.unwrap();
+        assert!(matches!(
+            &image.contents[0],
+            ResourceContents::BlobResourceContents { blob, .. } if blob == "iVBOR/8="
+        ));
+        assert!(resources.read_resource(&uris[2]).await.is_ok());
+        let localhost = uris[2].replacen("file://", "file://localhost", 1);
+        assert!(resources.read_resource(&localhost).await.is_ok());
+        let remote = uris[2].replacen("file://", "file://example.com", 1);
+        assert!(resources.read_resource(&remote).await.is_err());
+
+        let outside_name = outside.file_name().unwrap().to_string_lossy();
+        let traversal = format!("file://{}/../{outside_name}/secret.txt", root.display());
+        assert!(resources.read_resource(&traversal).await.is_err());
+        let escape = FileSystemResources::uri_of(&root.join("escape/secret.txt")).unwrap();
+        assert!(resources.read_resource(&escape).await.is_err());
+    }
+
+    #[test]
+    fn test_watched_changes_are_coalesced() {
+        use notify::event::{CreateKind, DataChange};
+
+        let root = temp_dir("watched");
+        let resources = FileSystemResources::new().with_root(&root).unwrap();
+        let root = std::fs::canonicalize(&root).unwrap();
+        let file = root.join("notes.md");
+        let event = |kind, path: &Path| Ok(notify::Event::new(kind).add_path(path.to_path_buf()));
+        let mut changes = WatchedChanges::default();
+        changes.add(
+            &resources,
+            event(EventKind::Create(CreateKind::File), &file),
+        );
+        for _ in 0..3 {
+            changes.add(
+                &resources,
+                event(
+                    EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+                    &file,
+                ),
+            );
+        }
+        // outside of the roots
+        changes.add(
+            &resources,
+            event(EventKind::Create(CreateKind::File), Path::new("/elsewhere")),
+        );
+        assert!(changes.list_changed);
+        assert_eq!(
+            changes.updated.into_iter().collect::<Vec<_>>(),
+            [FileSystemResources::uri_of(&file).unwrap()]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+/// A parsed [RFC 6570](https://www.rfc-editor.org/rfc/rfc6570) URI template.
+///
+/// The template can be matched against a concrete uri to extract the values of its
+/// variables. Simple (`{var}`), reserved (`{+var}`), fragment (`{#var}`), label (`{.var}`),
+/// path segment (`{/var}`), path parameter (`{;var}`) and query (`{?var}`, `{&var}`)
+/// expressions are supported. Prefix (`{var:3}`) and explode (`{var*}`) modifiers are accepted,
+/// but they don't change how a uri is matched.
+///
+/// # Example
+/// ```rust
+/// # use rmcp::handler::server::resource::UriTemplate;
+/// let template = UriTemplate::parse("repo://{owner}/{name}/issues{?state}").unwrap();
+/// let variables = template.match_uri("repo://rust-lang/rust/issues?state=open").unwrap();
+/// assert_eq!(variables["owner"], "rust-lang");
+/// assert_eq!(variables["name"], "rust");
+/// assert_eq!(variables["state"], "open");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Expression {
+        operator: Operator,
+        variables: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    Path,
+    PathParameter,
+    Query,
+    QueryContinuation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UriTemplateError {
+    #[error("unclosed expression at {0}")]
+    UnclosedExpression(usize),
+    #[error("empty expression at {0}")]
+    EmptyExpression(usize),
+    #[error("invalid variable name `{0}`")]
+    InvalidVariable(String),
+}
+
+fn is_reserved(c: char) -> bool {
+    matches!(
+        c,
+        ':' | '/'
+            | '?'
+            | '#'
+            | '['
+            | ']'
+            | '@'
+            | '!'
+            | '$'
+            | '&'
+            | '\''
+            | '('
+            | ')'
+            | '*'
+            | '+'
+            | ','
+            | ';'
+            | '='
+    )
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = input.get(index + 1..index + 3)?;
+            // `from_str_radix` would accept a sign, as in `%+1`
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+impl Operator {
+    fn from_prefix(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Self::Reserved),
+            '#' => Some(Self::Fragment),
+            '.' => Some(Self::Label),
+            '/' => Some(Self::Path),
+            ';' => Some(Self::PathParameter),
+            '?' => Some(Self::Query),
+            '&' => Some(Self::QueryContinuation),
+            _ => None,
+        }
+    }
+
+    /// The character this expression starts with when it expands to a non-empty value
+    fn prefix(self) -> Option<char> {
+        match self {
+            Self::Simple | Self::Reserved => None,
+            Self::Fragment => Some('#'),
+            Self::Label => Some('.'),
+            Self::Path => Some('/'),
+            Self::PathParameter => Some(';'),
+            Self::Query => Some('?'),
+            Self::QueryContinuation => Some('&'),
+        }
+    }
+
+    fn allows(self, c: char) -> bool {
+        match self {
+            Self::Simple | Self::Label => !is_reserved(c) || c == ',',
+            Self::Path => !is_reserved(c) || c == ',' || c == '/',
+            Self::Reserved | Self::Fragment => true,
+            Self::PathParameter => !matches!(c, '/' | '?' | '#'),
+            Self::Query | Self::QueryContinuation => c != '#',
+        }
+    }
+
+    /// Extract the variables from the part of the uri matched by this expression
+    fn capture(
+        self,
+        span: &str,
+        names: &[String],
+        captured: &mut HashMap<String, String>,
+    ) -> Option<()> {
+        let body = match self.prefix() {
+            None if span.is_empty() => return None,
+            None => span,
+            Some(_) if span.is_empty() => return Some(()),
+            Some(prefix) => span.strip_prefix(prefix)?,
+        };
+        match self {
+            Self::PathParameter | Self::Query | Self::QueryContinuation => {
+                let separator = if self == Self::PathParameter {
+                    ';'
+                } else {
+                    '&'
+                };
+                for pair in body.split(separator) {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    if names.iter().any(|name| name == key) {
+                        captured.insert(key.to_owned(), percent_decode(value)?);
+                    }
+                }
+            }
+            _ => {
+                let separator = match self {
+                    Self::Label => '.',
+                    Self::Path => '/',
+                    _ => ',',
+                };
+                for (name, value) in names.iter().zip(body.splitn(names.len(), separator)) {
+                    captured.insert(name.clone(), percent_decode(value)?);
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+fn parse_expression(expression: &str, position: usize) -> Result<Part, UriTemplateError> {
+    let mut chars = expression.chars();
+    let (operator, variable_list) = match chars.next().and_then(Operator::from_prefix) {
+        Some(operator) => (operator, chars.as_str()),
+        None => (Operator::Simple, expression),
+    };
+    if variable_list.is_empty() {
+        return Err(UriTemplateError::EmptyExpression(position));
+    }
+    let variables = variable_list
+        .split(',')
+        .map(|spec| {
+            let name = match spec.split_once(':') {
+                Some((name, length)) if length.parse::<u16>().is_ok() => name,
+                Some(_) => return Err(UriTemplateError::InvalidVariable(spec.to_owned())),
+                None => spec.strip_suffix('*').unwrap_or(spec),
+            };
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '%');
+            if valid {
+                Ok(name.to_owned())
+            } else {
+                Err(UriTemplateError::InvalidVariable(spec.to_owned()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Part::Expression {
+        operator,
+        variables,
+    })
+}
+
+/// Whether the parts match the whole input, the variables are captured on success.
+///
+/// An expression tries the longest span first and backtracks on failure. The positions where
+/// the remaining parts are known not to match are kept in `failed`, whatever was captured
+/// before them, so that each is tried once and matching stays polynomial.
+fn match_parts(
+    parts: &[Part],
+    input: &str,
+    variables: &mut HashMap<String, String>,
+    failed: &mut HashSet<(usize, usize)>,
+) -> bool {
+    let state = (parts.len(), input.len());
+    if failed.contains(&state) {
+        return false;
+    }
+    let matched = match_first_part(parts, input, variables, failed);
+    if !matched {
+        failed.insert(state);
+    }
+    matched
+}
+
+fn match_first_part(
+    parts: &[Part],
+    input: &str,
+    variables: &mut HashMap<String, String>,
+    failed: &mut HashSet<(usize, usize)>,
+) -> bool {
+    let Some((first, rest)) = parts.split_first() else {
+        return input.is_empty();
+    };
+    match first {
+        Part::Literal(literal) => input
+            .strip_prefix(literal.as_str())
+            .is_some_and(|remain| match_parts(rest, remain, variables, failed)),
+        Part::Expression {
+            operator,
+            variables: names,
+        } => {
+            let mut max_end = 0;
+            for (index, c) in input.char_indices() {
+                // the leading operator character is always allowed
+                if (index == 0 && operator.prefix() == Some(c)) || operator.allows(c) {
+                    max_end = index + c.len_utf8();
+                } else {
+                    break;
+                }
+            }
+            // the span can only end where the next literal starts
+            let next_literal = match rest.first() {
+                Some(Part::Literal(next)) => Some(next.as_str()),
+                _ => None,
+            };
+            // try the longest span first, backtrack on failure
+            for end in (0..=max_end).rev() {
+                if !input.is_char_boundary(end) {
+                    continue;
+                }
+                if next_literal.is_some_and(|next| !input[end..].starts_with(next)) {
+                    continue;
+                }
+                let mut captured = HashMap::new();
+                if operator
+                    .capture(&input[..end], names, &mut captured)
+                    .is_none()
+                {
+                    continue;
+                }
+                if match_parts(rest, &input[end..], variables, failed) {
+                    // a variable repeated later in the template keeps its last value
+                    for (name, value) in captured {
+                        variables.entry(name).or_insert(value);
+                    }
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+impl UriTemplate {
+    pub fn parse(template: impl Into<String>) -> Result<Self, UriTemplateError> {
+        let template = template.into();
+        let mut parts = Vec::new();
+        let mut rest = template.as_str();
+        let mut offset = 0;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_owned()));
+            }
+            let after = &rest[start + 1..];
+            let end = after
+                .find('}')
+                .ok_or(UriTemplateError::UnclosedExpression(offset + start))?;
+            parts.push(parse_expression(&after[..end], offset + start)?);
+            offset += start + end + 2;
+            rest = &after[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_owned()));
+        }
+        Ok(Self { template, parts })
+    }
+
+    /// The original template string
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Names of all variables in the template, in order of appearance
+    pub fn variable_names(&self) -> impl Iterator<Item = &str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Literal(_) => Default::default(),
+                Part::Expression { variables, .. } => variables.as_slice(),
+            })
+            .map(String::as_str)
+    }
+
+    /// Match a uri against this template.
+    ///
+    /// Returns the percent-decoded values of the variables present in the uri, or `None` if
+    /// the uri doesn't match.
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        let mut variables = HashMap::new();
+        match_parts(&self.parts, uri, &mut variables, &mut HashSet::new()).then_some(variables)
+    }
+
+    pub fn is_match(&self, uri: &str) -> bool {
+        self.match_uri(uri).is_some()
+    }
+}
+
+impl FromStr for UriTemplate {
+    type Err = UriTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.template.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(template: &str, uri: &str) -> Option<HashMap<String, String>> {
+        UriTemplate::parse(template)
+            .expect("valid template")
+            .match_uri(uri)
+    }
+
+    #[test]
+    fn test_simple_expression() {
+        let variables = matched("users://{id}/profile", "users://42/profile").unwrap();
+        assert_eq!(variables["id"], "42");
+        assert!(matched("users://{id}/profile", "users:///profile").is_none());
+        assert!(matched("users://{id}/profile", "users://a/b/profile").is_none());
+        let variables = matched("file:///{name}", "file:///hello%20world").unwrap();
+        assert_eq!(variables["name"], "hello world");
+        assert!(matched("file:///{name}", "file:///a%+1").is_none());
+        assert!(matched("file:///{name}", "file:///a%2").is_none());
+    }
+
+    #[test]
+    fn test_reserved_and_path_expression() {
+        let variables = matched("file:///{+path}.rs", "file:///src/lib.rs").unwrap();
+        assert_eq!(variables["path"], "src/lib");
+        let variables = matched("repo://x{/owner,name}", "repo://x/keipes/mcp").unwrap();
+        assert_eq!(variables["owner"], "keipes");
+        assert_eq!(variables["name"], "mcp");
+    }
+
+    #[test]
+    fn test_query_expression() {
+        let template = "search://items{?q,limit}";
+        let variables = matched(template, "search://items?limit=10&q=a%2Cb").unwrap();
+        assert_eq!(variables["q"], "a,b");
+        assert_eq!(variables["limit"], "10");
+        let variables = matched(template, "search://items").unwrap();
+        assert!(variables.is_empty());
+    }
+
+    #[test]
+    fn test_backtracking_is_bounded() {
+        let template = "x://{+a}/{+b}/{+c}/{+d}/{+e}/{+f}/{+g}/{+h}.json";
+        // every split of the segments is a candidate, there are C(60, 7) of them
+        let uri = format!("x://{}", "a/".repeat(60));
+        let start = std::time::Instant::now();
+        assert!(matched(template, &uri).is_none());
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        let variables = matched(template, "x://a/b/c/d/e/f/g/h/i.json").unwrap();
+        assert_eq!(variables["a"], "a/b");
+        assert_eq!(variables["h"], "i");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            UriTemplate::parse("file:///{path"),
+            Err(UriTemplateError::UnclosedExpression(8))
+        );
+        assert_eq!(
+            UriTemplate::parse("file:///{}"),
+            Err(UriTemplateError::EmptyExpression(8))
+        );
+        assert!(matches!(
+            UriTemplate::parse("file:///{a b}"),
+            Err(UriTemplateError::InvalidVariable(_))
+        ));
+    }
+}
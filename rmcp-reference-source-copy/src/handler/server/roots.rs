@@ -0,0 +1,240 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use super::tool::{FromToolCallContextPart, ToolCallContext};
+use crate::{
+    RoleServer,
+    model::Root,
+    service::{Peer, ServiceError},
+};
+
+/// The roots of a client, as they were last fetched.
+///
+/// Tools can take it as a parameter to check the paths they are given, which requires a
+/// [`RootsTracker`] set with [`Router::with_roots_tracker`](crate::handler::server::router::Router::with_roots_tracker)
+/// or inserted in the request extensions.
+#[derive(Debug, Clone, Default)]
+pub struct ClientRoots {
+    roots: Arc<[Root]>,
+    /// The normalized paths of the `file://` roots
+    paths: Arc<[PathBuf]>,
+}
+
+impl ClientRoots {
+    pub fn new(roots: Vec<Root>) -> Self {
+        let paths = roots
+            .iter()
+            .filter_map(|root| url::Url::parse(&root.uri).ok())
+            .filter(|url| url.scheme() == "file")
+            // percent-decoded, and only a local host is accepted
+            .filter_map(|url| url.to_file_path().ok())
+            .filter_map(|path| resolve_path(&path))
+            .collect();
+        Self {
+            roots: roots.into(),
+            paths,
+        }
+    }
+
+    pub fn roots(&self) -> &[Root] {
+        &self.roots
+    }
+
+    /// Whether the path is under one of the `file://` roots, after resolving `..` and symbolic links
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.resolve(path).is_ok()
+    }
+
+    /// Resolve a path under the roots, a relative path is relative to the first root.
+    ///
+    /// Fails with an invalid params error when the path is outside of all the roots.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, crate::ErrorData> {
+        let path = path.as_ref();
+        let outside = || {
+            crate::ErrorData::invalid_params(
+                format!("{} is outside of the client roots", path.display()),
+                None,
+            )
+        };
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.paths.first().ok_or_else(outside)?.join(path)
+        };
+        match resolve_path(&absolute) {
+            Some(resolved) if self.paths.iter().any(|root| resolved.starts_with(root)) => {
+                Ok(resolved)
+            }
+            _ => Err(outside()),
+        }
+    }
+}
+
+/// Resolve the `.` and `..` components and the symbolic links, one component at a time so that
+/// a `..` applies to where a link leads. The components past the existing part of the path are
+/// kept as they are.
+///
+/// `None` if the path goes through a broken link, whose target can't be checked.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                match std::fs::canonicalize(&resolved) {
+                    Ok(canonical) => resolved = canonical,
+                    Err(_) if resolved.symlink_metadata().is_ok() => return None,
+                    Err(_) => {}
+                }
+            }
+            component => resolved.push(component),
+        }
+    }
+    Some(resolved)
+}
+
+#[derive(Debug)]
+struct TrackedPeer {
+    peer: Peer<RoleServer>,
+    roots: ClientRoots,
+}
+
+/// Keep the roots of the connected clients up to date.
+///
+/// The roots are fetched once the client is initialized, and fetched again when the client
+/// sends `notifications/roots/list_changed`. A [`Router`](crate::handler::server::router::Router)
+/// does it with [`Router::with_roots_tracker`](crate::handler::server::router::Router::with_roots_tracker),
+/// otherwise call [`RootsTracker::refresh`] from [`ServerHandler::on_initialized`](crate::ServerHandler::on_initialized)
+/// and [`ServerHandler::on_roots_list_changed`](crate::ServerHandler::on_roots_list_changed).
+#[derive(Debug, Clone, Default)]
+pub struct RootsTracker {
+    peers: Arc<Mutex<Vec<TrackedPeer>>>,
+}
+
+impl RootsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<TrackedPeer>> {
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Fetch the roots of a client, a client without the `roots` capability has no root
+    pub async fn refresh(&self, peer: &Peer<RoleServer>) -> Result<ClientRoots, ServiceError> {
+        let supports_roots = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        let roots = if supports_roots {
+            ClientRoots::new(peer.list_roots().await?.roots)
+        } else {
+            ClientRoots::default()
+        };
+        let mut peers = self.lock();
+        if let Some(tracked) = peers.iter_mut().find(|t| t.peer.is_same_peer(peer)) {
+            tracked.roots = roots.clone();
+            return Ok(roots);
+        }
+        peers.push(TrackedPeer {
+            peer: peer.clone(),
+            roots: roots.clone(),
+        });
+        drop(peers);
+        // forget the peer once it's gone
+        let tracker = self.clone();
+        let peer = peer.clone();
+        tokio::spawn(async move {
+            peer.transport_closed().await;
+            tracker.lock().retain(|t| !t.peer.is_same_peer(&peer));
+        });
+        Ok(roots)
+    }
+
+    /// The last fetched roots of a client, empty if they haven't been fetched
+    pub fn roots(&self, peer: &Peer<RoleServer>) -> ClientRoots {
+        self.lock()
+            .iter()
+            .find(|t| t.peer.is_same_peer(peer))
+            .map(|t| t.roots.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl<S> FromToolCallContextPart<S> for ClientRoots {
+    fn from_tool_call_context_part(
+        context: &mut ToolCallContext<S>,
+    ) -> Result<Self, crate::ErrorData> {
+        let tracker = context
+            .request_context
+            .extensions
+            .get::<RootsTracker>()
+            .ok_or_else(|| crate::ErrorData::internal_error("roots are not tracked", None))?;
+        Ok(tracker.roots(&context.request_context.peer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(path: &Path) -> Root {
+        Root {
+            uri: url::Url::from_file_path(path).unwrap().into(),
+            name: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_within_roots() {
+        let workspace =
+            std::env::temp_dir().join(format!("rmcp-roots-workspace-{}", std::process::id()));
+        let outside =
+            std::env::temp_dir().join(format!("rmcp-roots-outside-{}", std::process::id()));
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        #[cfg(unix)]
+        let _ = std::os::unix::fs::symlink(&outside, workspace.join("link"));
+        #[cfg(unix)]
+        let _ = std::os::unix::fs::symlink(outside.join("missing"), workspace.join("broken"));
+
+        let roots = ClientRoots::new(vec![
+            root(&workspace),
+            Root {
+                uri: "https://example.com".into(),
+                name: None,
+            },
+        ]);
+        let workspace = std::fs::canonicalize(&workspace).unwrap();
+        assert_eq!(
+            roots.resolve("src/main.rs").unwrap(),
+            workspace.join("src/main.rs")
+        );
+        assert!(roots.contains(workspace.join("src/../new/file.txt")));
+        assert!(!roots.contains(workspace.join("../etc/passwd")));
+        assert!(!roots.contains("/etc/passwd"));
+        #[cfg(unix)]
+        {
+            assert!(!roots.contains(workspace.join("link/file.txt")));
+            // `..` applies to the target of the link, not to the link
+            assert!(!roots.contains(workspace.join("link/../file.txt")));
+            assert!(!roots.contains(workspace.join("broken")));
+        }
+        assert!(roots.contains(workspace.join("new/../src/main.rs")));
+
+        assert!(!ClientRoots::default().contains("file.txt"));
+
+        // the uri of a root is percent-decoded
+        let spaced = workspace.join("with space");
+        std::fs::create_dir_all(&spaced).unwrap();
+        let roots = ClientRoots::new(vec![root(&spaced)]);
+        assert!(roots.contains(spaced.join("file.txt")));
+    }
+}
@@ -1,18 +1,46 @@
 use std::sync::Arc;
 
+use completion::CompletionRouter;
+use prompt::{IntoPromptRoute, PromptRoute};
+use registry::{PromptRegistry, Registry, ResourceRegistry, ToolRegistry};
+use resource::{
+    IntoResourceRoute, IntoResourceTemplateRoute, InvalidResourceTemplate, ResourceRoute,
+    ResourceTemplateRoute,
+};
 use tool::{IntoToolRoute, ToolRoute};
 
-use super::ServerHandler;
+use super::{
+    ServerHandler, completion::CompletionProvider, pagination::Paginator, roots::RootsTracker,
+    subscription::SubscriptionManager,
+};
 use crate::{
     RoleServer, Service,
-    model::{ClientRequest, ListToolsResult, ServerResult},
+    model::{
+        ClientNotification, ClientRequest, Cursor, ErrorCode, JsonObject, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+        PromptsCapability, ResourcesCapability, ServerResult, ToolsCapability,
+    },
     service::NotificationContext,
 };
 
+pub mod completion;
+pub mod prompt;
+pub mod registry;
+pub mod resource;
 pub mod tool;
 
 pub struct Router<S> {
     pub tool_router: tool::ToolRouter<S>,
+    pub prompt_router: prompt::PromptRouter<S>,
+    pub resource_router: resource::ResourceRouter<S>,
+    pub completion_router: CompletionRouter<S>,
+    pub subscriptions: Option<SubscriptionManager>,
+    pub roots_tracker: Option<RootsTracker>,
+    pub tool_registry: Option<ToolRegistry<S>>,
+    pub prompt_registry: Option<PromptRegistry<S>>,
+    pub resource_registry: Option<ResourceRegistry<S>>,
+    pub paginator: Option<Paginator>,
+    pub logging: bool,
     pub service: Arc<S>,
 }
 
@@ -23,6 +51,16 @@ where
     pub fn new(service: S) -> Self {
         Self {
             tool_router: tool::ToolRouter::new(),
+            prompt_router: prompt::PromptRouter::new(),
+            resource_router: resource::ResourceRouter::new(),
+            completion_router: CompletionRouter::new(),
+            subscriptions: None,
+            roots_tracker: None,
+            tool_registry: None,
+            prompt_registry: None,
+            resource_registry: None,
+            paginator: None,
+            logging: false,
             service: Arc::new(service),
         }
     }
@@ -41,6 +79,161 @@ where
         }
         self
     }
+
+    pub fn with_prompt<R, A>(mut self, route: R) -> Self
+    where
+        R: IntoPromptRoute<S, A>,
+    {
+        self.prompt_router.add_route(route.into_prompt_route());
+        self
+    }
+
+    pub fn with_prompts(mut self, routes: impl IntoIterator<Item = PromptRoute<S>>) -> Self {
+        for route in routes {
+            self.prompt_router.add_route(route);
+        }
+        self
+    }
+
+    pub fn with_resource<R, A>(mut self, route: R) -> Self
+    where
+        R: IntoResourceRoute<S, A>,
+    {
+        self.resource_router.add_route(route.into_resource_route());
+        self
+    }
+
+    pub fn with_resources(mut self, routes: impl IntoIterator<Item = ResourceRoute<S>>) -> Self {
+        for route in routes {
+            self.resource_router.add_route(route);
+        }
+        self
+    }
+
+    /// # Panics
+    /// Panics if the uri template is invalid, see [`Self::try_with_resource_template`].
+    pub fn with_resource_template<R, A>(self, route: R) -> Self
+    where
+        R: IntoResourceTemplateRoute<S, A>,
+    {
+        self.try_with_resource_template(route)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_with_resource_template<R, A>(
+        mut self,
+        route: R,
+    ) -> Result<Self, InvalidResourceTemplate>
+    where
+        R: IntoResourceTemplateRoute<S, A>,
+    {
+        self.resource_router
+            .add_template_route(route.into_resource_template_route()?);
+        Ok(self)
+    }
+
+    pub fn with_resource_templates(
+        mut self,
+        routes: impl IntoIterator<Item = ResourceTemplateRoute<S>>,
+    ) -> Self {
+        for route in routes {
+            self.resource_router.add_template_route(route);
+        }
+        self
+    }
+
+    pub fn with_completions(mut self, completion_router: CompletionRouter<S>) -> Self {
+        self.completion_router.merge(completion_router);
+        self
+    }
+
+    /// Track `resources/subscribe` and `resources/unsubscribe` with the manager, which is
+    /// then used to notify the subscribers
+    pub fn with_subscriptions(mut self, subscriptions: SubscriptionManager) -> Self {
+        self.subscriptions = Some(subscriptions);
+        self
+    }
+
+    /// Keep the roots of the client up to date with the tracker, which is also made available
+    /// to the handlers through the request extensions
+    pub fn with_roots_tracker(mut self, roots_tracker: RootsTracker) -> Self {
+        self.roots_tracker = Some(roots_tracker);
+        self
+    }
+
+    /// Serve the tools from the registry instead of the tool router, the connected clients
+    /// are notified when the registry changes
+    pub fn with_tool_registry(mut self, tool_registry: ToolRegistry<S>) -> Self {
+        self.tool_registry = Some(tool_registry);
+        self
+    }
+
+    /// Serve the prompts from the registry instead of the prompt router, the connected clients
+    /// are notified when the registry changes
+    pub fn with_prompt_registry(mut self, prompt_registry: PromptRegistry<S>) -> Self {
+        self.prompt_registry = Some(prompt_registry);
+        self
+    }
+
+    /// Serve the resources from the registry instead of the resource router, the connected
+    /// clients are notified when the registry changes
+    pub fn with_resource_registry(mut self, resource_registry: ResourceRegistry<S>) -> Self {
+        self.resource_registry = Some(resource_registry);
+        self
+    }
+
+    /// Split the lists of tools, prompts, resources and resource templates into pages,
+    /// they are sent whole otherwise
+    pub fn with_pagination(mut self, paginator: Paginator) -> Self {
+        self.paginator = Some(paginator);
+        self
+    }
+
+    /// Advertise the `logging` capability and accept `logging/setLevel`, for a server
+    /// forwarding its logs to the client, e.g. with the
+    /// [`LoggingLayer`](crate::handler::server::logging::LoggingLayer)
+    pub fn with_logging(mut self) -> Self {
+        self.logging = true;
+        self
+    }
+
+    fn paginate<T>(
+        &self,
+        list: &str,
+        items: Vec<T>,
+        key: impl Fn(&T) -> &str,
+        params: Option<PaginatedRequestParam>,
+    ) -> Result<(Vec<T>, Option<Cursor>), crate::ErrorData> {
+        match &self.paginator {
+            Some(paginator) => {
+                let cursor = params.and_then(|params| params.cursor);
+                paginator.paginate(list, items, key, cursor.as_deref())
+            }
+            None => Ok((items, None)),
+        }
+    }
+
+    pub fn with_prompt_completion(
+        mut self,
+        prompt: impl Into<String>,
+        argument: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) -> Self {
+        self.completion_router
+            .add_prompt_argument(prompt, argument, provider);
+        self
+    }
+
+    pub fn with_resource_completion(
+        mut self,
+        uri_template: impl Into<String>,
+        variable: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) -> Self {
+        self.completion_router
+            .add_resource_variable(uri_template, variable, provider);
+        self
+    }
 }
 
 impl<S> Service<RoleServer> for Router<S>
@@ -52,6 +245,29 @@ where
         notification: <RoleServer as crate::service::ServiceRole>::PeerNot,
         context: NotificationContext<RoleServer>,
     ) -> Result<(), crate::ErrorData> {
+        // the roots are fetched before the service is notified, so that it sees them
+        if let (
+            Some(roots_tracker),
+            ClientNotification::InitializedNotification(_)
+            | ClientNotification::RootsListChangedNotification(_),
+        ) = (&self.roots_tracker, &notification)
+        {
+            let refreshed = roots_tracker.refresh(&context.peer).await;
+            if let Err(error) = refreshed {
+                tracing::warn!(%error, "failed to fetch the client roots");
+            }
+        }
+        if let ClientNotification::InitializedNotification(_) = &notification {
+            if let Some(tool_registry) = &self.tool_registry {
+                tool_registry.bind(context.peer.clone());
+            }
+            if let Some(prompt_registry) = &self.prompt_registry {
+                prompt_registry.bind(context.peer.clone());
+            }
+            if let Some(resource_registry) = &self.resource_registry {
+                resource_registry.bind(context.peer.clone());
+            }
+        }
         self.service
             .handle_notification(notification, context)
             .await
@@ -59,19 +275,31 @@ where
     async fn handle_request(
         &self,
         request: <RoleServer as crate::service::ServiceRole>::PeerReq,
-        context: crate::service::RequestContext<RoleServer>,
+        mut context: crate::service::RequestContext<RoleServer>,
     ) -> Result<<RoleServer as crate::service::ServiceRole>::Resp, crate::ErrorData> {
+        if let Some(roots_tracker) = &self.roots_tracker {
+            context.extensions.insert(roots_tracker.clone());
+        }
+        // the requests are served from a snapshot, so that the registries can change meanwhile
+        let tool_snapshot = self.tool_registry.as_ref().map(Registry::snapshot);
+        let tool_router = tool_snapshot.as_deref().unwrap_or(&self.tool_router);
+        let prompt_snapshot = self.prompt_registry.as_ref().map(Registry::snapshot);
+        let prompt_router = prompt_snapshot.as_deref().unwrap_or(&self.prompt_router);
+        let resource_snapshot = self.resource_registry.as_ref().map(Registry::snapshot);
+        let resource_router = resource_snapshot
+            .as_deref()
+            .unwrap_or(&self.resource_router);
         match request {
             ClientRequest::CallToolRequest(request) => {
-                if self.tool_router.has_route(request.params.name.as_ref())
-                    || !self.tool_router.transparent_when_not_found
+                if tool_router.has_route(request.params.name.as_ref())
+                    || !tool_router.transparent_when_not_found
                 {
                     let tool_call_context = crate::handler::server::tool::ToolCallContext::new(
                         self.service.as_ref(),
                         request.params,
                         context,
                     );
-                    let result = self.tool_router.call(tool_call_context).await?;
+                    let result = tool_router.call(tool_call_context).await?;
                     Ok(ServerResult::CallToolResult(result))
                 } else {
                     self.service
@@ -79,18 +307,185 @@ where
                         .await
                 }
             }
-            ClientRequest::ListToolsRequest(_) => {
-                let tools = self.tool_router.list_all();
+            ClientRequest::ListToolsRequest(request) => {
+                let (tools, next_cursor) = self.paginate(
+                    "tools",
+                    tool_router.list_all(),
+                    |tool| tool.name.as_ref(),
+                    request.params,
+                )?;
                 Ok(ServerResult::ListToolsResult(ListToolsResult {
                     tools,
-                    next_cursor: None,
+                    next_cursor,
+                }))
+            }
+            // an empty prompt router leaves prompts to the inner service
+            ClientRequest::GetPromptRequest(request) if !prompt_router.is_empty() => {
+                if prompt_router.has_route(&request.params.name)
+                    || !prompt_router.transparent_when_not_found
+                {
+                    let prompt_context = crate::handler::server::prompt::PromptContext::new(
+                        self.service.as_ref(),
+                        request.params,
+                        context,
+                    );
+                    let result = prompt_router.get_prompt(prompt_context).await?;
+                    Ok(ServerResult::GetPromptResult(result))
+                } else {
+                    self.service
+                        .handle_request(ClientRequest::GetPromptRequest(request), context)
+                        .await
+                }
+            }
+            ClientRequest::ListPromptsRequest(request) if !prompt_router.is_empty() => {
+                let (prompts, next_cursor) = self.paginate(
+                    "prompts",
+                    prompt_router.list_all(),
+                    |prompt| prompt.name.as_str(),
+                    request.params,
+                )?;
+                Ok(ServerResult::ListPromptsResult(ListPromptsResult {
+                    prompts,
+                    next_cursor,
+                }))
+            }
+            // an empty resource router leaves resources to the inner service
+            ClientRequest::ReadResourceRequest(request) if !resource_router.is_empty() => {
+                if resource_router.has_route(&request.params.uri)
+                    || !resource_router.transparent_when_not_found
+                {
+                    let resource_context = crate::handler::server::resource::ResourceContext::new(
+                        self.service.as_ref(),
+                        request.params,
+                        context,
+                    );
+                    let result = resource_router.read_resource(resource_context).await?;
+                    Ok(ServerResult::ReadResourceResult(result))
+                } else {
+                    self.service
+                        .handle_request(ClientRequest::ReadResourceRequest(request), context)
+                        .await
+                }
+            }
+            ClientRequest::ListResourcesRequest(request) if !resource_router.is_empty() => {
+                let (resources, next_cursor) = self.paginate(
+                    "resources",
+                    resource_router.list_all(),
+                    |resource| resource.uri.as_str(),
+                    request.params,
+                )?;
+                Ok(ServerResult::ListResourcesResult(ListResourcesResult {
+                    resources,
+                    next_cursor,
                 }))
             }
+            ClientRequest::ListResourceTemplatesRequest(request) if !resource_router.is_empty() => {
+                let (resource_templates, next_cursor) = self.paginate(
+                    "resource_templates",
+                    resource_router.list_all_templates(),
+                    |template| template.uri_template.as_str(),
+                    request.params,
+                )?;
+                Ok(ServerResult::ListResourceTemplatesResult(
+                    ListResourceTemplatesResult {
+                        resource_templates,
+                        next_cursor,
+                    },
+                ))
+            }
+            ClientRequest::SubscribeRequest(request) => match &self.subscriptions {
+                Some(subscriptions) => {
+                    subscriptions.subscribe(&context.peer, request.params.uri);
+                    Ok(ServerResult::empty(()))
+                }
+                None => {
+                    self.service
+                        .handle_request(ClientRequest::SubscribeRequest(request), context)
+                        .await
+                }
+            },
+            ClientRequest::UnsubscribeRequest(request) => match &self.subscriptions {
+                Some(subscriptions) => {
+                    subscriptions.unsubscribe(&context.peer, &request.params.uri);
+                    Ok(ServerResult::empty(()))
+                }
+                None => {
+                    self.service
+                        .handle_request(ClientRequest::UnsubscribeRequest(request), context)
+                        .await
+                }
+            },
+            // the level is stored for the session, so that the logging layer can honor it
+            ClientRequest::SetLevelRequest(request) => {
+                context.peer.set_logging_level(request.params.level);
+                match self
+                    .service
+                    .handle_request(ClientRequest::SetLevelRequest(request), context)
+                    .await
+                {
+                    Err(error) if self.logging && error.code == ErrorCode::METHOD_NOT_FOUND => {
+                        Ok(ServerResult::empty(()))
+                    }
+                    result => result,
+                }
+            }
+            // an empty completion router leaves completions to the inner service
+            ClientRequest::CompleteRequest(request) if !self.completion_router.is_empty() => {
+                if self
+                    .completion_router
+                    .has_route(&request.params.r#ref, &request.params.argument.name)
+                    || !self.completion_router.transparent_when_not_found
+                {
+                    let completion_context =
+                        crate::handler::server::completion::CompletionContext::new(
+                            self.service.as_ref(),
+                            request.params,
+                            context,
+                        );
+                    let result = self.completion_router.complete(completion_context).await?;
+                    Ok(ServerResult::CompleteResult(result))
+                } else {
+                    self.service
+                        .handle_request(ClientRequest::CompleteRequest(request), context)
+                        .await
+                }
+            }
             rest => self.service.handle_request(rest, context).await,
         }
     }
 
     fn get_info(&self) -> <RoleServer as crate::service::ServiceRole>::Info {
-        self.service.get_info()
+        let mut info = self.service.get_info();
+        if !self.completion_router.is_empty() && info.capabilities.completions.is_none() {
+            info.capabilities.completions = Some(JsonObject::new());
+        }
+        if self.subscriptions.is_some() {
+            info.capabilities
+                .resources
+                .get_or_insert_with(ResourcesCapability::default)
+                .subscribe = Some(true);
+        }
+        if self.tool_registry.is_some() {
+            info.capabilities
+                .tools
+                .get_or_insert_with(ToolsCapability::default)
+                .list_changed = Some(true);
+        }
+        if self.prompt_registry.is_some() {
+            info.capabilities
+                .prompts
+                .get_or_insert_with(PromptsCapability::default)
+                .list_changed = Some(true);
+        }
+        if self.resource_registry.is_some() {
+            info.capabilities
+                .resources
+                .get_or_insert_with(ResourcesCapability::default)
+                .list_changed = Some(true);
+        }
+        if self.logging && info.capabilities.logging.is_none() {
+            info.capabilities.logging = Some(JsonObject::new());
+        }
+        info
     }
 }
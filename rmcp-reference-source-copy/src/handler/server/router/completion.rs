@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use crate::{
+    handler::server::completion::{CompletionContext, CompletionProvider, empty_completion_result},
+    model::{CompleteResult, Reference},
+};
+
+/// Routes `completion/complete` to the provider registered for the argument.
+///
+/// Prompt arguments are registered by prompt name, resource template variables by uri
+/// template. A request for an argument without provider gets an empty result.
+#[derive(Debug)]
+pub struct CompletionRouter<S> {
+    pub prompts: HashMap<String, HashMap<String, CompletionProvider<S>>>,
+    pub resource_templates: HashMap<String, HashMap<String, CompletionProvider<S>>>,
+
+    pub transparent_when_not_found: bool,
+}
+
+impl<S> Default for CompletionRouter<S> {
+    fn default() -> Self {
+        Self {
+            prompts: HashMap::new(),
+            resource_templates: HashMap::new(),
+            transparent_when_not_found: false,
+        }
+    }
+}
+
+impl<S> Clone for CompletionRouter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            prompts: self.prompts.clone(),
+            resource_templates: self.resource_templates.clone(),
+            transparent_when_not_found: self.transparent_when_not_found,
+        }
+    }
+}
+
+impl<S> CompletionRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_prompt_argument(
+        mut self,
+        prompt: impl Into<String>,
+        argument: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) -> Self {
+        self.add_prompt_argument(prompt, argument, provider);
+        self
+    }
+    pub fn with_resource_variable(
+        mut self,
+        uri_template: impl Into<String>,
+        variable: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) -> Self {
+        self.add_resource_variable(uri_template, variable, provider);
+        self
+    }
+
+    pub fn add_prompt_argument(
+        &mut self,
+        prompt: impl Into<String>,
+        argument: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) {
+        self.prompts
+            .entry(prompt.into())
+            .or_default()
+            .insert(argument.into(), provider);
+    }
+    pub fn add_resource_variable(
+        &mut self,
+        uri_template: impl Into<String>,
+        variable: impl Into<String>,
+        provider: CompletionProvider<S>,
+    ) {
+        self.resource_templates
+            .entry(uri_template.into())
+            .or_default()
+            .insert(variable.into(), provider);
+    }
+
+    pub fn merge(&mut self, other: CompletionRouter<S>) {
+        for (prompt, arguments) in other.prompts {
+            self.prompts.entry(prompt).or_default().extend(arguments);
+        }
+        for (uri_template, variables) in other.resource_templates {
+            self.resource_templates
+                .entry(uri_template)
+                .or_default()
+                .extend(variables);
+        }
+    }
+
+    pub fn remove_prompt(&mut self, prompt: &str) {
+        self.prompts.remove(prompt);
+    }
+    pub fn remove_resource_template(&mut self, uri_template: &str) {
+        self.resource_templates.remove(uri_template);
+    }
+
+    fn provider(&self, reference: &Reference, argument: &str) -> Option<&CompletionProvider<S>> {
+        match reference {
+            Reference::Prompt(prompt) => self.prompts.get(&prompt.name),
+            Reference::Resource(resource) => self.resource_templates.get(&resource.uri),
+        }
+        .and_then(|arguments| arguments.get(argument))
+    }
+    /// Whether a provider is registered for the argument
+    pub fn has_route(&self, reference: &Reference, argument: &str) -> bool {
+        self.provider(reference, argument).is_some()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty() && self.resource_templates.is_empty()
+    }
+
+    pub async fn complete(
+        &self,
+        context: CompletionContext<'_, S>,
+    ) -> Result<CompleteResult, crate::ErrorData> {
+        match self.provider(&context.reference, &context.argument.name) {
+            Some(provider) => provider.complete(context).await,
+            None => Ok(empty_completion_result()),
+        }
+    }
+}
+
+impl<S> std::ops::Add<CompletionRouter<S>> for CompletionRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Output = Self;
+
+    fn add(mut self, other: CompletionRouter<S>) -> Self::Output {
+        self.merge(other);
+        self
+    }
+}
+
+impl<S> std::ops::AddAssign<CompletionRouter<S>> for CompletionRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn add_assign(&mut self, other: CompletionRouter<S>) {
+        self.merge(other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        RoleServer,
+        model::{ArgumentInfo, CompleteRequestParam, PromptReference, ResourceReference},
+        service::test_util,
+    };
+
+    async fn complete(
+        router: &CompletionRouter<()>,
+        reference: Reference,
+        name: &str,
+        value: &str,
+    ) -> CompleteResult {
+        let request_context = test_util::request_context::<RoleServer>();
+        let param = CompleteRequestParam {
+            r#ref: reference,
+            argument: ArgumentInfo {
+                name: name.into(),
+                value: value.into(),
+            },
+        };
+        router
+            .complete(CompletionContext::new(&(), param, request_context))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_complete() {
+        let router = CompletionRouter::<()>::new()
+            .with_prompt_argument(
+                "greet",
+                "language",
+                CompletionProvider::prefix(["English", "Esperanto", "French"]),
+            )
+            .with_resource_variable(
+                "file:///{path}",
+                "path",
+                CompletionProvider::from_fn(|argument: ArgumentInfo, _context| async move {
+                    Ok((0..150).map(|i| format!("{}{i}", argument.value)).collect())
+                }),
+            );
+        let prompt = Reference::Prompt(PromptReference {
+            name: "greet".into(),
+        });
+        let resource = Reference::Resource(ResourceReference {
+            uri: "file:///{path}".into(),
+        });
+        assert!(router.has_route(&prompt, "language"));
+        assert!(!router.has_route(&prompt, "name"));
+
+        let result = complete(&router, prompt.clone(), "language", "e").await;
+        assert_eq!(result.completion.values, ["English", "Esperanto"]);
+        assert_eq!(result.completion.total, Some(2));
+        assert_eq!(result.completion.has_more, Some(false));
+
+        let result = complete(&router, prompt, "name", "a").await;
+        assert!(result.completion.values.is_empty());
+
+        let result = complete(&router, resource, "path", "src/").await;
+        assert_eq!(result.completion.values.len(), 100);
+        assert_eq!(result.completion.values[0], "src/0");
+        assert_eq!(result.completion.total, Some(150));
+        assert_eq!(result.completion.has_more, Some(true));
+    }
+}
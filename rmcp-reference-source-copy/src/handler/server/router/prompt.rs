@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use futures::{FutureExt, future::BoxFuture};
+use schemars::JsonSchema;
+
+use crate::{
+    handler::server::prompt::{
+        DynGetPromptHandler, GetPromptHandler, PromptContext, arguments_from_schema,
+    },
+    model::{GetPromptResult, Prompt, PromptArgument},
+};
+
+pub struct PromptRoute<S> {
+    #[allow(clippy::type_complexity)]
+    pub get: Arc<DynGetPromptHandler<S>>,
+    pub attr: crate::model::Prompt,
+}
+
+impl<S> std::fmt::Debug for PromptRoute<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PromptRoute")
+            .field("name", &self.attr.name)
+            .field("description", &self.attr.description)
+            .field("arguments", &self.attr.arguments)
+            .finish()
+    }
+}
+
+impl<S> Clone for PromptRoute<S> {
+    fn clone(&self) -> Self {
+        Self {
+            get: self.get.clone(),
+            attr: self.attr.clone(),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> PromptRoute<S> {
+    pub fn new<H, A>(attr: impl Into<Prompt>, handler: H) -> Self
+    where
+        H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+    {
+        Self {
+            get: Arc::new(move |context: PromptContext<S>| {
+                let handler = handler.clone();
+                context.invoke(handler).boxed()
+            }),
+            attr: attr.into(),
+        }
+    }
+    pub fn new_dyn<H>(attr: impl Into<Prompt>, handler: H) -> Self
+    where
+        H: for<'a> Fn(
+                PromptContext<'a, S>,
+            ) -> BoxFuture<'a, Result<GetPromptResult, crate::ErrorData>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            get: Arc::new(handler),
+            attr: attr.into(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.attr.name
+    }
+}
+
+pub trait IntoPromptRoute<S, A> {
+    fn into_prompt_route(self) -> PromptRoute<S>;
+}
+
+impl<S, H, A, T> IntoPromptRoute<S, A> for (T, H)
+where
+    S: Send + Sync + 'static,
+    H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+    T: Into<Prompt>,
+{
+    fn into_prompt_route(self) -> PromptRoute<S> {
+        PromptRoute::new(self.0.into(), self.1)
+    }
+}
+
+impl<S> IntoPromptRoute<S, ()> for PromptRoute<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn into_prompt_route(self) -> PromptRoute<S> {
+        self
+    }
+}
+
+pub struct PromptAttrGenerateFunctionAdapter;
+impl<S, F> IntoPromptRoute<S, PromptAttrGenerateFunctionAdapter> for F
+where
+    S: Send + Sync + 'static,
+    F: Fn() -> PromptRoute<S>,
+{
+    fn into_prompt_route(self) -> PromptRoute<S> {
+        (self)()
+    }
+}
+
+pub trait GetPromptHandlerExt<S, A>: Sized
+where
+    Self: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+{
+    fn name(self, name: impl Into<String>) -> WithPromptAttr<Self, S, A>;
+}
+
+impl<H, S, A> GetPromptHandlerExt<S, A> for H
+where
+    H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+{
+    fn name(self, name: impl Into<String>) -> WithPromptAttr<Self, S, A> {
+        WithPromptAttr {
+            attr: Prompt::new(name, None::<String>, None),
+            handler: self,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+pub struct WithPromptAttr<H, S, A>
+where
+    H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+{
+    pub attr: crate::model::Prompt,
+    pub handler: H,
+    pub _marker: std::marker::PhantomData<fn(S, A)>,
+}
+
+impl<H, S, A> IntoPromptRoute<S, A> for WithPromptAttr<H, S, A>
+where
+    H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+    S: Send + Sync + 'static,
+{
+    fn into_prompt_route(self) -> PromptRoute<S> {
+        PromptRoute::new(self.attr, self.handler)
+    }
+}
+
+impl<H, S, A> WithPromptAttr<H, S, A>
+where
+    H: GetPromptHandler<S, A> + Send + Sync + Clone + 'static,
+{
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.attr.description = Some(description.into());
+        self
+    }
+    /// Derive the prompt arguments from the JSON schema of `T`, see [`arguments_from_schema`].
+    pub fn arguments<T: JsonSchema>(mut self) -> Self {
+        self.attr.arguments = arguments_from_schema::<T>();
+        self
+    }
+    pub fn arguments_value(mut self, arguments: Vec<PromptArgument>) -> Self {
+        self.attr.arguments = Some(arguments);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct PromptRouter<S> {
+    #[allow(clippy::type_complexity)]
+    pub map: std::collections::HashMap<String, PromptRoute<S>>,
+
+    pub transparent_when_not_found: bool,
+}
+
+impl<S> Default for PromptRouter<S> {
+    fn default() -> Self {
+        Self {
+            map: std::collections::HashMap::new(),
+            transparent_when_not_found: false,
+        }
+    }
+}
+impl<S> Clone for PromptRouter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            transparent_when_not_found: self.transparent_when_not_found,
+        }
+    }
+}
+
+impl<S> IntoIterator for PromptRouter<S> {
+    type Item = PromptRoute<S>;
+    type IntoIter = std::collections::hash_map::IntoValues<String, PromptRoute<S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_values()
+    }
+}
+
+impl<S> PromptRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            map: std::collections::HashMap::new(),
+            transparent_when_not_found: false,
+        }
+    }
+    pub fn with_route<R, A>(mut self, route: R) -> Self
+    where
+        R: IntoPromptRoute<S, A>,
+    {
+        self.add_route(route.into_prompt_route());
+        self
+    }
+
+    pub fn add_route(&mut self, item: PromptRoute<S>) {
+        self.map.insert(item.attr.name.clone(), item);
+    }
+
+    pub fn merge(&mut self, other: PromptRouter<S>) {
+        for item in other.map.into_values() {
+            self.add_route(item);
+        }
+    }
+
+    /// Whether there was a route to remove
+    pub fn remove_route(&mut self, name: &str) -> bool {
+        self.map.remove(name).is_some()
+    }
+    pub fn has_route(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    pub async fn get_prompt(
+        &self,
+        context: PromptContext<'_, S>,
+    ) -> Result<GetPromptResult, crate::ErrorData> {
+        let item = self
+            .map
+            .get(context.name())
+            .ok_or_else(|| crate::ErrorData::invalid_params("prompt not found", None))?;
+        (item.get)(context).await
+    }
+
+    pub fn list_all(&self) -> Vec<crate::model::Prompt> {
+        self.map.values().map(|item| item.attr.clone()).collect()
+    }
+}
+
+impl<S> std::ops::Add<PromptRouter<S>> for PromptRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Output = Self;
+
+    fn add(mut self, other: PromptRouter<S>) -> Self::Output {
+        self.merge(other);
+        self
+    }
+}
+
+impl<S> std::ops::AddAssign<PromptRouter<S>> for PromptRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn add_assign(&mut self, other: PromptRouter<S>) {
+        self.merge(other);
+    }
+}
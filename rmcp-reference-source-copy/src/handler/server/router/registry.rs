@@ -0,0 +1,240 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use futures::future::{BoxFuture, join_all};
+
+use super::{
+    prompt::{PromptRoute, PromptRouter},
+    resource::{ResourceRoute, ResourceRouter, ResourceTemplateRoute},
+    tool::{ToolRoute, ToolRouter},
+};
+use crate::{
+    RoleServer,
+    service::{Peer, ServiceError},
+};
+
+/// A router whose changes are announced with a `list_changed` notification
+pub trait ListChangedRouter: Clone + Send + Sync + 'static {
+    fn notify_list_changed(peer: &Peer<RoleServer>) -> BoxFuture<'_, Result<(), ServiceError>>;
+}
+
+impl<S: Send + Sync + 'static> ListChangedRouter for ToolRouter<S> {
+    fn notify_list_changed(peer: &Peer<RoleServer>) -> BoxFuture<'_, Result<(), ServiceError>> {
+        Box::pin(peer.notify_tool_list_changed())
+    }
+}
+
+impl<S: Send + Sync + 'static> ListChangedRouter for PromptRouter<S> {
+    fn notify_list_changed(peer: &Peer<RoleServer>) -> BoxFuture<'_, Result<(), ServiceError>> {
+        Box::pin(peer.notify_prompt_list_changed())
+    }
+}
+
+impl<S: Send + Sync + 'static> ListChangedRouter for ResourceRouter<S> {
+    fn notify_list_changed(peer: &Peer<RoleServer>) -> BoxFuture<'_, Result<(), ServiceError>> {
+        Box::pin(peer.notify_resource_list_changed())
+    }
+}
+
+/// A router that can be changed while the server runs.
+///
+/// The requests are served from a snapshot of the router, so a change doesn't wait for the
+/// requests in progress. After each change, the bound peers are sent a `list_changed`
+/// notification. [`Router`](super::Router) binds the peers it serves once they are initialized,
+/// and tells them `list_changed: true` in its capabilities.
+///
+/// # Example
+/// ```rust,ignore
+/// let tools = ToolRegistry::new(ToolRouter::new());
+/// let router = Router::new(server).with_tool_registry(tools.clone());
+/// // later, once a plugin is loaded
+/// tools.add_route(plugin_tool).await;
+/// ```
+#[derive(Debug)]
+pub struct Registry<T> {
+    router: Arc<RwLock<Arc<T>>>,
+    peers: Arc<Mutex<Vec<Peer<RoleServer>>>>,
+}
+
+impl<T> Clone for Registry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+            peers: self.peers.clone(),
+        }
+    }
+}
+
+pub type ToolRegistry<S> = Registry<ToolRouter<S>>;
+pub type PromptRegistry<S> = Registry<PromptRouter<S>>;
+pub type ResourceRegistry<S> = Registry<ResourceRouter<S>>;
+
+impl<T: ListChangedRouter> Registry<T> {
+    pub fn new(router: T) -> Self {
+        Self {
+            router: Arc::new(RwLock::new(Arc::new(router))),
+            peers: Default::default(),
+        }
+    }
+
+    /// The router as it is now
+    pub fn snapshot(&self) -> Arc<T> {
+        self.router
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Notify a peer of the changes, until its transport is closed
+    pub fn bind(&self, peer: Peer<RoleServer>) {
+        let mut peers = self
+            .peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        peers.retain(|bound| !bound.is_transport_closed());
+        if !peers.iter().any(|bound| bound.is_same_peer(&peer)) {
+            peers.push(peer);
+        }
+    }
+
+    /// Change the router, then notify the bound peers.
+    ///
+    /// `f` returns whether it changed anything, the peers aren't notified otherwise.
+    pub async fn update(&self, f: impl FnOnce(&mut T) -> bool) {
+        let changed = {
+            let mut router = self
+                .router
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(Arc::make_mut(&mut router))
+        };
+        if changed {
+            self.notify_list_changed().await;
+        }
+    }
+
+    /// Send a `list_changed` notification to the bound peers
+    pub async fn notify_list_changed(&self) {
+        let peers = {
+            let mut peers = self
+                .peers
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            peers.retain(|peer| !peer.is_transport_closed());
+            peers.clone()
+        };
+        let results = join_all(peers.iter().map(T::notify_list_changed)).await;
+        for error in results.into_iter().filter_map(Result::err) {
+            tracing::warn!(%error, "failed to notify list change");
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> Registry<ToolRouter<S>> {
+    /// Add or replace a tool
+    pub async fn add_route(&self, route: ToolRoute<S>) {
+        self.update(|router| {
+            router.add_route(route);
+            true
+        })
+        .await
+    }
+    pub async fn remove_route(&self, name: &str) {
+        self.update(|router| router.remove_route(name)).await
+    }
+}
+
+impl<S: Send + Sync + 'static> Registry<PromptRouter<S>> {
+    /// Add or replace a prompt
+    pub async fn add_route(&self, route: PromptRoute<S>) {
+        self.update(|router| {
+            router.add_route(route);
+            true
+        })
+        .await
+    }
+    pub async fn remove_route(&self, name: &str) {
+        self.update(|router| router.remove_route(name)).await
+    }
+}
+
+impl<S: Send + Sync + 'static> Registry<ResourceRouter<S>> {
+    /// Add or replace a resource
+    pub async fn add_route(&self, route: ResourceRoute<S>) {
+        self.update(|router| {
+            router.add_route(route);
+            true
+        })
+        .await
+    }
+    /// Add or replace a resource template
+    pub async fn add_template_route(&self, route: ResourceTemplateRoute<S>) {
+        self.update(|router| {
+            router.add_template_route(route);
+            true
+        })
+        .await
+    }
+    pub async fn remove_route(&self, uri: &str) {
+        self.update(|router| router.remove_route(uri)).await
+    }
+    pub async fn remove_template_route(&self, uri_template: &str) {
+        self.update(|router| router.remove_template_route(uri_template))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{JsonObject, ServerNotification, Tool},
+        service::{PeerSinkMessage, test_util},
+    };
+
+    #[tokio::test]
+    async fn test_changes_are_broadcast() {
+        let registry = ToolRegistry::<()>::new(ToolRouter::new());
+        let (peer, mut peer_rx) = test_util::peer::<RoleServer>();
+        registry.bind(peer.clone());
+        registry.bind(peer);
+        let snapshot = registry.snapshot();
+
+        let receiver = tokio::spawn(async move {
+            let mut received = 0;
+            while let Some(PeerSinkMessage::Notification {
+                notification,
+                responder,
+            }) = peer_rx.recv().await
+            {
+                assert!(matches!(
+                    notification,
+                    ServerNotification::ToolListChangedNotification(_)
+                ));
+                let _ = responder.send(Ok(()));
+                received += 1;
+                if received == 2 {
+                    break;
+                }
+            }
+            received
+        });
+        let tool = Tool::new("echo", "echo", Arc::new(JsonObject::new()));
+        registry
+            .add_route(ToolRoute::new_dyn(tool, |_| {
+                Box::pin(async { Ok(crate::model::CallToolResult::success(vec![])) })
+            }))
+            .await;
+        assert!(registry.snapshot().has_route("echo"));
+        // the snapshots taken before are left untouched
+        assert!(!snapshot.has_route("echo"));
+        registry.remove_route("echo").await;
+        assert!(!registry.snapshot().has_route("echo"));
+        assert_eq!(receiver.await.unwrap(), 2);
+
+        // nothing to remove, nothing to notify
+        let (peer, mut peer_rx) = test_util::peer::<RoleServer>();
+        registry.bind(peer);
+        registry.remove_route("echo").await;
+        assert!(peer_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,470 @@
+use std::sync::Arc;
+
+use futures::{FutureExt, future::BoxFuture};
+
+use crate::{
+    handler::server::resource::{
+        DynReadResourceHandler, ReadResourceHandler, ResourceContext, UriTemplate, UriTemplateError,
+    },
+    model::{ReadResourceResult, Resource, ResourceTemplate},
+};
+
+fn dyn_read_resource_handler<S, H, A>(handler: H) -> Arc<DynReadResourceHandler<S>>
+where
+    S: Send + Sync + 'static,
+    H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+{
+    Arc::new(move |context: ResourceContext<S>| {
+        let handler = handler.clone();
+        context.invoke(handler).boxed()
+    })
+}
+
+/// A route serving a single resource with a fixed uri
+pub struct ResourceRoute<S> {
+    #[allow(clippy::type_complexity)]
+    pub read: Arc<DynReadResourceHandler<S>>,
+    pub attr: Resource,
+}
+
+impl<S> std::fmt::Debug for ResourceRoute<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceRoute")
+            .field("uri", &self.attr.uri)
+            .field("name", &self.attr.name)
+            .finish()
+    }
+}
+
+impl<S> Clone for ResourceRoute<S> {
+    fn clone(&self) -> Self {
+        Self {
+            read: self.read.clone(),
+            attr: self.attr.clone(),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> ResourceRoute<S> {
+    pub fn new<H, A>(attr: impl Into<Resource>, handler: H) -> Self
+    where
+        H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+    {
+        Self {
+            read: dyn_read_resource_handler(handler),
+            attr: attr.into(),
+        }
+    }
+    pub fn new_dyn<H>(attr: impl Into<Resource>, handler: H) -> Self
+    where
+        H: for<'a> Fn(
+                ResourceContext<'a, S>,
+            ) -> BoxFuture<'a, Result<ReadResourceResult, crate::ErrorData>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            read: Arc::new(handler),
+            attr: attr.into(),
+        }
+    }
+    pub fn uri(&self) -> &str {
+        &self.attr.uri
+    }
+}
+
+/// A route serving every uri that matches a resource template
+pub struct ResourceTemplateRoute<S> {
+    #[allow(clippy::type_complexity)]
+    pub read: Arc<DynReadResourceHandler<S>>,
+    pub attr: ResourceTemplate,
+    pub template: UriTemplate,
+}
+
+impl<S> std::fmt::Debug for ResourceTemplateRoute<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceTemplateRoute")
+            .field("uri_template", &self.attr.uri_template)
+            .field("name", &self.attr.name)
+            .finish()
+    }
+}
+
+impl<S> Clone for ResourceTemplateRoute<S> {
+    fn clone(&self) -> Self {
+        Self {
+            read: self.read.clone(),
+            attr: self.attr.clone(),
+            template: self.template.clone(),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> ResourceTemplateRoute<S> {
+    /// # Panics
+    /// Panics if `attr.uri_template` is not a valid uri template, see [`Self::try_new`].
+    pub fn new<H, A>(attr: impl Into<ResourceTemplate>, handler: H) -> Self
+    where
+        H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+    {
+        Self::try_new(attr, handler).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_new<H, A>(
+        attr: impl Into<ResourceTemplate>,
+        handler: H,
+    ) -> Result<Self, InvalidResourceTemplate>
+    where
+        H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+    {
+        Self::new_with_read(attr.into(), dyn_read_resource_handler(handler))
+    }
+    /// # Panics
+    /// Panics if `attr.uri_template` is not a valid uri template, see [`Self::try_new_dyn`].
+    pub fn new_dyn<H>(attr: impl Into<ResourceTemplate>, handler: H) -> Self
+    where
+        H: for<'a> Fn(
+                ResourceContext<'a, S>,
+            ) -> BoxFuture<'a, Result<ReadResourceResult, crate::ErrorData>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::try_new_dyn(attr, handler).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_new_dyn<H>(
+        attr: impl Into<ResourceTemplate>,
+        handler: H,
+    ) -> Result<Self, InvalidResourceTemplate>
+    where
+        H: for<'a> Fn(
+                ResourceContext<'a, S>,
+            ) -> BoxFuture<'a, Result<ReadResourceResult, crate::ErrorData>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::new_with_read(attr.into(), Arc::new(handler))
+    }
+    fn new_with_read(
+        attr: ResourceTemplate,
+        read: Arc<DynReadResourceHandler<S>>,
+    ) -> Result<Self, InvalidResourceTemplate> {
+        let template = UriTemplate::parse(attr.uri_template.as_str()).map_err(|error| {
+            InvalidResourceTemplate {
+                uri_template: attr.uri_template.clone(),
+                error,
+            }
+        })?;
+        Ok(Self {
+            read,
+            attr,
+            template,
+        })
+    }
+    pub fn uri_template(&self) -> &str {
+        &self.attr.uri_template
+    }
+}
+
+pub trait IntoResourceRoute<S, A> {
+    fn into_resource_route(self) -> ResourceRoute<S>;
+}
+
+impl<S, H, A, T> IntoResourceRoute<S, A> for (T, H)
+where
+    S: Send + Sync + 'static,
+    H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+    T: Into<Resource>,
+{
+    fn into_resource_route(self) -> ResourceRoute<S> {
+        ResourceRoute::new(self.0.into(), self.1)
+    }
+}
+
+impl<S> IntoResourceRoute<S, ()> for ResourceRoute<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn into_resource_route(self) -> ResourceRoute<S> {
+        self
+    }
+}
+
+pub trait IntoResourceTemplateRoute<S, A> {
+    fn into_resource_template_route(
+        self,
+    ) -> Result<ResourceTemplateRoute<S>, InvalidResourceTemplate>;
+}
+
+impl<S, H, A, T> IntoResourceTemplateRoute<S, A> for (T, H)
+where
+    S: Send + Sync + 'static,
+    H: ReadResourceHandler<S, A> + Send + Sync + Clone + 'static,
+    T: Into<ResourceTemplate>,
+{
+    fn into_resource_template_route(
+        self,
+    ) -> Result<ResourceTemplateRoute<S>, InvalidResourceTemplate> {
+        ResourceTemplateRoute::try_new(self.0.into(), self.1)
+    }
+}
+
+impl<S> IntoResourceTemplateRoute<S, ()> for ResourceTemplateRoute<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn into_resource_template_route(
+        self,
+    ) -> Result<ResourceTemplateRoute<S>, InvalidResourceTemplate> {
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid uri template `{uri_template}`: {error}")]
+pub struct InvalidResourceTemplate {
+    pub uri_template: String,
+    #[source]
+    pub error: UriTemplateError,
+}
+
+/// Routes `resources/read` to handlers by uri.
+///
+/// A uri is first looked up in the fixed resources, then matched against the templates in
+/// the order they were added.
+#[derive(Debug)]
+pub struct ResourceRouter<S> {
+    pub map: std::collections::HashMap<String, ResourceRoute<S>>,
+    pub templates: Vec<ResourceTemplateRoute<S>>,
+
+    pub transparent_when_not_found: bool,
+}
+
+impl<S> Default for ResourceRouter<S> {
+    fn default() -> Self {
+        Self {
+            map: std::collections::HashMap::new(),
+            templates: Vec::new(),
+            transparent_when_not_found: false,
+        }
+    }
+}
+
+impl<S> Clone for ResourceRouter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            templates: self.templates.clone(),
+            transparent_when_not_found: self.transparent_when_not_found,
+        }
+    }
+}
+
+impl<S> ResourceRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_route<R, A>(mut self, route: R) -> Self
+    where
+        R: IntoResourceRoute<S, A>,
+    {
+        self.add_route(route.into_resource_route());
+        self
+    }
+    /// # Panics
+    /// Panics if the uri template is invalid, see [`Self::try_with_template_route`].
+    pub fn with_template_route<R, A>(self, route: R) -> Self
+    where
+        R: IntoResourceTemplateRoute<S, A>,
+    {
+        self.try_with_template_route(route)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_with_template_route<R, A>(
+        mut self,
+        route: R,
+    ) -> Result<Self, InvalidResourceTemplate>
+    where
+        R: IntoResourceTemplateRoute<S, A>,
+    {
+        self.add_template_route(route.into_resource_template_route()?);
+        Ok(self)
+    }
+
+    pub fn add_route(&mut self, item: ResourceRoute<S>) {
+        self.map.insert(item.attr.uri.clone(), item);
+    }
+
+    /// Add a template route, a route with the same uri template will be replaced.
+    pub fn add_template_route(&mut self, item: ResourceTemplateRoute<S>) {
+        match self
+            .templates
+            .iter_mut()
+            .find(|route| route.attr.uri_template == item.attr.uri_template)
+        {
+            Some(route) => *route = item,
+            None => self.templates.push(item),
+        }
+    }
+
+    pub fn merge(&mut self, other: ResourceRouter<S>) {
+        for item in other.map.into_values() {
+            self.add_route(item);
+        }
+        for item in other.templates {
+            self.add_template_route(item);
+        }
+    }
+
+    /// Whether there was a route to remove
+    pub fn remove_route(&mut self, uri: &str) -> bool {
+        self.map.remove(uri).is_some()
+    }
+    /// Whether there was a template route to remove
+    pub fn remove_template_route(&mut self, uri_template: &str) -> bool {
+        let len = self.templates.len();
+        self.templates
+            .retain(|route| route.attr.uri_template != uri_template);
+        self.templates.len() != len
+    }
+    /// Whether the uri is served by a resource or matches a template
+    pub fn has_route(&self, uri: &str) -> bool {
+        self.map.contains_key(uri) || self.templates.iter().any(|t| t.template.is_match(uri))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty() && self.templates.is_empty()
+    }
+
+    pub async fn read_resource(
+        &self,
+        mut context: ResourceContext<'_, S>,
+    ) -> Result<ReadResourceResult, crate::ErrorData> {
+        if let Some(item) = self.map.get(context.uri()) {
+            return (item.read)(context).await;
+        }
+        for item in &self.templates {
+            if let Some(variables) = item.template.match_uri(context.uri()) {
+                context.variables = variables;
+                return (item.read)(context).await;
+            }
+        }
+        Err(crate::ErrorData::resource_not_found(
+            "resource not found",
+            Some(serde_json::json!({ "uri": context.uri })),
+        ))
+    }
+
+    pub fn list_all(&self) -> Vec<Resource> {
+        self.map.values().map(|item| item.attr.clone()).collect()
+    }
+
+    pub fn list_all_templates(&self) -> Vec<ResourceTemplate> {
+        self.templates
+            .iter()
+            .map(|item| item.attr.clone())
+            .collect()
+    }
+}
+
+impl<S> std::ops::Add<ResourceRouter<S>> for ResourceRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Output = Self;
+
+    fn add(mut self, other: ResourceRouter<S>) -> Self::Output {
+        self.merge(other);
+        self
+    }
+}
+
+impl<S> std::ops::AddAssign<ResourceRouter<S>> for ResourceRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn add_assign(&mut self, other: ResourceRouter<S>) {
+        self.merge(other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        RoleServer,
+        model::{
+            AnnotateAble, ErrorCode, RawResource, RawResourceTemplate, ReadResourceRequestParam,
+            ResourceContents,
+        },
+        service::test_util,
+    };
+
+    fn describe(
+        context: ResourceContext<'_, ()>,
+    ) -> BoxFuture<'_, Result<ReadResourceResult, crate::ErrorData>> {
+        let text = match context.variables.get("name") {
+            Some(name) => format!("template {name}"),
+            None => "fixed".to_owned(),
+        };
+        Box::pin(std::future::ready(Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, context.uri)],
+        })))
+    }
+
+    fn template(uri_template: &str) -> ResourceTemplate {
+        RawResourceTemplate {
+            uri_template: uri_template.into(),
+            name: "users".into(),
+            description: None,
+            mime_type: None,
+        }
+        .no_annotation()
+    }
+
+    async fn read(router: &ResourceRouter<()>, uri: &str) -> Result<String, crate::ErrorData> {
+        let context = ResourceContext::new(
+            &(),
+            ReadResourceRequestParam { uri: uri.into() },
+            test_util::request_context::<RoleServer>(),
+        );
+        let result = router.read_resource(context).await?;
+        match result.contents.into_iter().next() {
+            Some(ResourceContents::TextResourceContents { text, .. }) => Ok(text),
+            _ => panic!("expect a text content"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_resource_dispatch() {
+        let router = ResourceRouter::<()>::new()
+            .with_template_route(ResourceTemplateRoute::new_dyn(
+                template("users://{name}"),
+                describe,
+            ))
+            .with_route(ResourceRoute::new_dyn(
+                RawResource::new("users://admin", "admin").no_annotation(),
+                describe,
+            ));
+        // the fixed resources come before the templates
+        assert_eq!(read(&router, "users://admin").await.unwrap(), "fixed");
+        assert_eq!(
+            read(&router, "users://alice").await.unwrap(),
+            "template alice"
+        );
+        let error = read(&router, "posts://1").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::RESOURCE_NOT_FOUND);
+
+        let invalid = ResourceTemplateRoute::<()>::try_new_dyn(template("users://{name"), describe);
+        assert!(matches!(
+            invalid,
+            Err(InvalidResourceTemplate {
+                error: UriTemplateError::UnclosedExpression(8),
+                ..
+            })
+        ));
+    }
+}
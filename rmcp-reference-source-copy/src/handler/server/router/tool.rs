@@ -1,19 +1,25 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    sync::{Arc, OnceLock},
+};
 
 use futures::{FutureExt, future::BoxFuture};
 use schemars::JsonSchema;
 
 use crate::{
-    handler::server::tool::{
-        CallToolHandler, DynCallToolHandler, ToolCallContext, schema_for_type,
+    handler::{
+        server::tool::{CallToolHandler, DynCallToolHandler, ToolCallContext, schema_for_type},
+        validation::{SchemaValidator, violations_error},
     },
-    model::{CallToolResult, Tool, ToolAnnotations},
+    model::{CallToolResult, JsonObject, Tool, ToolAnnotations},
 };
 
 pub struct ToolRoute<S> {
     #[allow(clippy::type_complexity)]
     pub call: Arc<DynCallToolHandler<S>>,
     pub attr: crate::model::Tool,
+    /// the compiled `attr.input_schema`, `None` if the schema is invalid
+    input_validator: Arc<OnceLock<Option<SchemaValidator>>>,
 }
 
 impl<S> std::fmt::Debug for ToolRoute<S> {
@@ -22,6 +28,7 @@ impl<S> std::fmt::Debug for ToolRoute<S> {
             .field("name", &self.attr.name)
             .field("description", &self.attr.description)
             .field("input_schema", &self.attr.input_schema)
+            .field("output_schema", &self.attr.output_schema)
             .finish()
     }
 }
@@ -31,21 +38,29 @@ impl<S> Clone for ToolRoute<S> {
         Self {
             call: self.call.clone(),
             attr: self.attr.clone(),
+            input_validator: self.input_validator.clone(),
         }
     }
 }
 
 impl<S: Send + Sync + 'static> ToolRoute<S> {
+    /// Create a route, if `attr` has no output schema, the one derived from the return
+    /// type of `call` will be used.
     pub fn new<C, A>(attr: impl Into<Tool>, call: C) -> Self
     where
         C: CallToolHandler<S, A> + Send + Sync + Clone + 'static,
     {
+        let mut attr = attr.into();
+        if attr.output_schema.is_none() {
+            attr.output_schema = C::output_schema();
+        }
         Self {
             call: Arc::new(move |context: ToolCallContext<S>| {
                 let call = call.clone();
                 context.invoke(call).boxed()
             }),
-            attr: attr.into(),
+            attr,
+            input_validator: Default::default(),
         }
     }
     pub fn new_dyn<C>(attr: impl Into<Tool>, call: C) -> Self
@@ -60,11 +75,35 @@ impl<S: Send + Sync + 'static> ToolRoute<S> {
         Self {
             call: Arc::new(call),
             attr: attr.into(),
+            input_validator: Default::default(),
         }
     }
     pub fn name(&self) -> &str {
         &self.attr.name
     }
+    /// Validate the arguments of a call against `attr.input_schema`.
+    ///
+    /// The schema is compiled on the first call and cached, so changes to `attr` made after
+    /// that are not taken into account. Missing arguments are validated as an empty object.
+    pub fn validate_arguments(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<(), crate::ErrorData> {
+        let validator = self.input_validator.get_or_init(|| {
+            SchemaValidator::new(&self.attr.input_schema)
+                .inspect_err(
+                    |e| tracing::warn!(tool = %self.attr.name, "arguments won't be validated: {e}"),
+                )
+                .ok()
+        });
+        let Some(validator) = validator else {
+            return Ok(());
+        };
+        let arguments = serde_json::Value::Object(arguments.cloned().unwrap_or_default());
+        validator
+            .validate(&arguments)
+            .map_err(|violations| violations_error("invalid tool arguments", violations))
+    }
 }
 
 pub trait IntoToolRoute<S, A> {
@@ -161,6 +200,14 @@ where
         self.attr.input_schema = crate::model::object(schema).into();
         self
     }
+    pub fn output_schema<T: JsonSchema>(mut self) -> Self {
+        self.attr.output_schema = Some(schema_for_type::<T>().into());
+        self
+    }
+    pub fn output_schema_value(mut self, schema: serde_json::Value) -> Self {
+        self.attr.output_schema = Some(crate::model::object(schema).into());
+        self
+    }
     pub fn annotation(mut self, annotation: impl Into<ToolAnnotations>) -> Self {
         self.attr.annotations = Some(annotation.into());
         self
@@ -228,8 +275,9 @@ where
         }
     }
 
-    pub fn remove_route<H, A>(&mut self, name: &str) {
-        self.map.remove(name);
+    /// Whether there was a route to remove
+    pub fn remove_route(&mut self, name: &str) -> bool {
+        self.map.remove(name).is_some()
     }
     pub fn has_route(&self, name: &str) -> bool {
         self.map.contains_key(name)
@@ -242,6 +290,7 @@ where
             .map
             .get(context.name())
             .ok_or_else(|| crate::ErrorData::invalid_params("tool not found", None))?;
+        item.validate_arguments(context.arguments.as_ref())?;
         (item.call)(context).await
     }
 
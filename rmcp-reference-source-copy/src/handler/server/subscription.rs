@@ -0,0 +1,171 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use futures::future::join_all;
+
+use crate::{
+    RoleServer,
+    model::ResourceUpdatedNotificationParam,
+    service::{Peer, ServiceError},
+};
+
+#[derive(Debug)]
+struct Subscriber {
+    peer: Peer<RoleServer>,
+    uris: HashSet<String>,
+}
+
+/// Track the resources each peer subscribed to, and send them `notifications/resources/updated`.
+///
+/// A peer is identified by its connection, so the same manager can be shared by every session
+/// of a server, whether it's served over stdio or through a session manager. A peer is
+/// forgotten once its transport is closed.
+///
+/// Give it to [`Router::with_subscriptions`](crate::handler::server::router::Router::with_subscriptions),
+/// or call [`SubscriptionManager::subscribe`] and [`SubscriptionManager::unsubscribe`] from
+/// your [`ServerHandler`](crate::ServerHandler).
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionManager {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Subscriber>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn subscribe(&self, peer: &Peer<RoleServer>, uri: impl Into<String>) {
+        if peer.is_transport_closed() {
+            return;
+        }
+        let uri = uri.into();
+        let mut subscribers = self.lock();
+        if let Some(subscriber) = subscribers.iter_mut().find(|s| s.peer.is_same_peer(peer)) {
+            subscriber.uris.insert(uri);
+            return;
+        }
+        subscribers.push(Subscriber {
+            peer: peer.clone(),
+            uris: HashSet::from([uri]),
+        });
+        drop(subscribers);
+        // forget the peer once it's gone
+        let manager = self.clone();
+        let peer = peer.clone();
+        tokio::spawn(async move {
+            peer.transport_closed().await;
+            manager.remove_peer(&peer);
+        });
+    }
+
+    pub fn unsubscribe(&self, peer: &Peer<RoleServer>, uri: &str) {
+        let mut subscribers = self.lock();
+        if let Some(subscriber) = subscribers.iter_mut().find(|s| s.peer.is_same_peer(peer)) {
+            subscriber.uris.remove(uri);
+        }
+    }
+
+    /// Drop all the subscriptions of a peer
+    pub fn remove_peer(&self, peer: &Peer<RoleServer>) {
+        self.lock().retain(|s| !s.peer.is_same_peer(peer));
+    }
+
+    pub fn is_subscribed(&self, peer: &Peer<RoleServer>, uri: &str) -> bool {
+        self.lock()
+            .iter()
+            .any(|s| s.peer.is_same_peer(peer) && s.uris.contains(uri))
+    }
+
+    /// The peers subscribed to a resource
+    pub fn subscribers(&self, uri: &str) -> Vec<Peer<RoleServer>> {
+        self.lock()
+            .iter()
+            .filter(|s| s.uris.contains(uri) && !s.peer.is_transport_closed())
+            .map(|s| s.peer.clone())
+            .collect()
+    }
+
+    /// Notify the subscribers of a resource that it has been updated.
+    ///
+    /// Returns the number of peers notified, the peers which can't be notified are forgotten.
+    pub async fn notify_updated(&self, uri: impl Into<String>) -> usize {
+        let uri = uri.into();
+        let subscribers = self.subscribers(&uri);
+        let results = join_all(subscribers.iter().map(|peer| {
+            peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri: uri.clone() })
+        }))
+        .await;
+        let mut notified = 0;
+        for (peer, result) in subscribers.iter().zip(results) {
+            match result {
+                Ok(()) => notified += 1,
+                Err(ServiceError::TransportClosed) => self.remove_peer(peer),
+                Err(error) => {
+                    tracing::warn!(%error, %uri, "failed to notify resource update");
+                }
+            }
+        }
+        notified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::ServerNotification,
+        service::{PeerSinkMessage, test_util::peer},
+    };
+
+    #[tokio::test]
+    async fn test_notify_only_subscribers() {
+        let manager = SubscriptionManager::new();
+        let (a, mut a_rx) = peer();
+        let (b, b_rx) = peer();
+        manager.subscribe(&a, "file:///a");
+        manager.subscribe(&b, "file:///b");
+        assert!(manager.is_subscribed(&a, "file:///a"));
+        assert!(!manager.is_subscribed(&b, "file:///a"));
+
+        let responder = tokio::spawn(async move {
+            let Some(PeerSinkMessage::Notification {
+                notification,
+                responder,
+            }) = a_rx.recv().await
+            else {
+                panic!("expect a notification");
+            };
+            let _ = responder.send(Ok(()));
+            (notification, a_rx)
+        });
+        assert_eq!(manager.notify_updated("file:///a").await, 1);
+        // keep a connected
+        let (notification, _a_rx) = responder.await.unwrap();
+        let ServerNotification::ResourceUpdatedNotification(notification) = notification else {
+            panic!("expect a resource updated notification");
+        };
+        assert_eq!(notification.params.uri, "file:///a");
+
+        manager.unsubscribe(&a, "file:///a");
+        assert_eq!(manager.notify_updated("file:///a").await, 0);
+
+        // a disconnected peer is forgotten
+        drop(b_rx);
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            while manager.is_subscribed(&b, "file:///b") {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("the disconnected peer must be removed");
+        assert_eq!(manager.lock().len(), 1);
+    }
+}
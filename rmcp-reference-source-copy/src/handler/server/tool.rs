@@ -99,6 +99,15 @@ pub trait FromToolCallContextPart<S>: Sized {
 
 pub trait IntoCallToolResult {
     fn into_call_tool_result(self) -> Result<CallToolResult, crate::ErrorData>;
+    /// The JSON schema of the structured content produced by this type, if it has any.
+    ///
+    /// This will be used as the output schema of a tool returning this type.
+    fn output_schema() -> Option<Arc<JsonObject>>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 impl<T: IntoContents> IntoCallToolResult for T {
@@ -123,6 +132,9 @@ impl<T: IntoCallToolResult> IntoCallToolResult for Result<T, crate::ErrorData> {
             Err(error) => Err(error),
         }
     }
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        T::output_schema()
+    }
 }
 
 pin_project_lite::pin_project! {
@@ -171,6 +183,13 @@ pub trait CallToolHandler<S, A> {
         self,
         context: ToolCallContext<'_, S>,
     ) -> BoxFuture<'_, Result<CallToolResult, crate::ErrorData>>;
+    /// The output schema of the tool, derived from the return type of the handler
+    fn output_schema() -> Option<Arc<JsonObject>>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 pub type DynCallToolHandler<S> = dyn for<'s> Fn(ToolCallContext<'s, S>) -> BoxFuture<'s, Result<CallToolResult, crate::ErrorData>>
@@ -348,6 +367,9 @@ macro_rules! impl_for {
             R: IntoCallToolResult + Send + 'static,
             S: Send + Sync + 'static,
         {
+            fn output_schema() -> Option<Arc<JsonObject>> {
+                R::output_schema()
+            }
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
@@ -379,6 +401,9 @@ macro_rules! impl_for {
             R: IntoCallToolResult + Send + 'static,
             S: Send + Sync,
         {
+            fn output_schema() -> Option<Arc<JsonObject>> {
+                R::output_schema()
+            }
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
@@ -408,6 +433,9 @@ macro_rules! impl_for {
             R: IntoCallToolResult + Send + ,
             S: Send + Sync,
         {
+            fn output_schema() -> Option<Arc<JsonObject>> {
+                R::output_schema()
+            }
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
@@ -433,6 +461,9 @@ macro_rules! impl_for {
             R: IntoCallToolResult + Send + ,
             S: Send + Sync,
         {
+            fn output_schema() -> Option<Arc<JsonObject>> {
+                R::output_schema()
+            }
             #[allow(unused_variables, non_snake_case, unused_mut)]
             fn call(
                 self,
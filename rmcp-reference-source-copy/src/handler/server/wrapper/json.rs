@@ -1,28 +1,99 @@
+use std::sync::Arc;
+
+use schemars::JsonSchema;
 use serde::Serialize;
 
-use crate::model::IntoContents;
+use crate::{
+    handler::server::tool::{IntoCallToolResult, cached_schema_for_type},
+    model::{CallToolResult, Content, IntoContents, JsonObject},
+};
 
 /// Json wrapper
 ///
-/// This is used to tell the SDK to serialize the inner value into json
+/// When returned from a tool, a value serialized into a json object is sent as the
+/// structured content of the result, along with its text for the clients which don't
+/// support structured content, and the schema of `T` is used as the output schema of the
+/// tool. Other values are sent as a json text content.
 pub struct Json<T>(pub T);
 
-impl<T> IntoContents for Json<T>
+/// Kept for compatibility, [`Json`] already produces structured content
+pub type Structured<T> = Json<T>;
+
+impl<T> IntoCallToolResult for Json<T>
 where
-    T: Serialize,
+    T: Serialize + JsonSchema + 'static,
 {
-    fn into_contents(self) -> Vec<crate::model::Content> {
-        let result = crate::model::Content::json(self.0);
-        debug_assert!(
-            result.is_ok(),
-            "Json wrapped content should be able to serialized into json"
-        );
-        match result {
-            Ok(content) => vec![content],
-            Err(e) => {
-                tracing::error!("failed to convert json content: {e}");
-                vec![]
-            }
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::ErrorData> {
+        let value = serde_json::to_value(self.0).map_err(|e| {
+            crate::ErrorData::internal_error(
+                format!("failed to serialize structured content: {e}"),
+                None,
+            )
+        })?;
+        match value {
+            serde_json::Value::Object(object) => Ok(CallToolResult::structured(object)),
+            // structured content must be an object
+            value => Ok(CallToolResult::success(vec![Content::text(
+                value.to_string(),
+            )])),
         }
     }
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        let schema = cached_schema_for_type::<T>();
+        (schema.get("type") == Some(&serde_json::Value::from("object"))).then_some(schema)
+    }
+}
+
+impl<T, E> IntoCallToolResult for Result<Json<T>, E>
+where
+    T: Serialize + JsonSchema + 'static,
+    E: IntoContents,
+{
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::ErrorData> {
+        match self {
+            Ok(value) => value.into_call_tool_result(),
+            Err(error) => Ok(CallToolResult::error(error.into_contents())),
+        }
+    }
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        Json::<T>::output_schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handler::server::router::tool::ToolRoute, model::Tool};
+
+    #[derive(Serialize, JsonSchema)]
+    struct Sum {
+        value: i32,
+    }
+
+    #[test]
+    fn test_structured_output() {
+        assert!(Json::<Sum>::output_schema().is_some());
+        assert!(Json::<i32>::output_schema().is_none());
+        let result = Json(Sum { value: 3 }).into_call_tool_result().unwrap();
+        assert_eq!(
+            result.structured_content,
+            crate::model::object(serde_json::json!({ "value": 3 })).into()
+        );
+        // the text fallback
+        assert_eq!(result.content[0].as_text().unwrap().text, r#"{"value":3}"#);
+        // a value which isn't an object stays a text content
+        let result = Json(3).into_call_tool_result().unwrap();
+        assert!(result.structured_content.is_none());
+    }
+
+    #[test]
+    fn test_route_output_schema() {
+        let route = ToolRoute::<()>::new(Tool::new("sum", "sum", JsonObject::new()), || async {
+            Json(Sum { value: 3 })
+        });
+        assert_eq!(
+            route.attr.output_schema,
+            Some(cached_schema_for_type::<Sum>())
+        );
+    }
 }
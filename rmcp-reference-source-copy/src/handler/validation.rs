@@ -0,0 +1,219 @@
+//! JSON Schema validation of tool arguments and structured results
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::model::{CallToolResult, ErrorData, JsonObject, Tool};
+
+/// A single place where a value doesn't conform to a schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending part of the value, empty for the value itself
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            self.pointer.as_str()
+        };
+        write!(f, "{pointer}: {}", self.message)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid json schema: {0}")]
+pub struct InvalidSchema(String);
+
+/// A compiled JSON schema, cheap to clone
+#[derive(Clone)]
+pub struct SchemaValidator {
+    validator: Arc<jsonschema::Validator>,
+}
+
+impl std::fmt::Debug for SchemaValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaValidator").finish_non_exhaustive()
+    }
+}
+
+impl SchemaValidator {
+    pub fn new(schema: &JsonObject) -> Result<Self, InvalidSchema> {
+        let validator = jsonschema::validator_for(&Value::Object(schema.clone()))
+            .map_err(|e| InvalidSchema(e.to_string()))?;
+        Ok(Self {
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// Validate a value, returning every violation found
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+        let violations: Vec<_> = self
+            .validator
+            .iter_errors(instance)
+            .map(|error| SchemaViolation {
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// Build an invalid params error, the violations are listed in the `violations` field of its data
+pub fn violations_error(message: &str, violations: Vec<SchemaViolation>) -> ErrorData {
+    ErrorData::invalid_params(
+        format!("{message}: {}", summary(&violations)),
+        Some(serde_json::json!({ "violations": violations })),
+    )
+}
+
+/// A tool result doesn't conform to the output schema of the tool
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid structured content of tool {tool}: {}", summary(violations))]
+pub struct InvalidToolOutput {
+    pub tool: String,
+    /// Empty when the result has no structured content at all
+    pub violations: Vec<SchemaViolation>,
+}
+
+fn summary(violations: &[SchemaViolation]) -> String {
+    if violations.is_empty() {
+        return "no structured content".to_string();
+    }
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Validate the structured content of tool results against the output schemas of the tools.
+///
+/// Build it from the tools listed by the server, and pass it to
+/// [`Peer::<RoleClient>::call_tool_validated`](crate::Peer::call_tool_validated).
+#[derive(Debug, Clone, Default)]
+pub struct ToolOutputValidator {
+    validators: HashMap<String, SchemaValidator>,
+}
+
+impl ToolOutputValidator {
+    /// Compile the output schemas of the tools, tools with an invalid output schema are skipped
+    pub fn new<'a>(tools: impl IntoIterator<Item = &'a Tool>) -> Self {
+        let mut validators = HashMap::new();
+        for tool in tools {
+            let Some(schema) = &tool.output_schema else {
+                continue;
+            };
+            match SchemaValidator::new(schema) {
+                Ok(validator) => {
+                    validators.insert(tool.name.to_string(), validator);
+                }
+                Err(e) => tracing::warn!(tool = %tool.name, "skip invalid output schema: {e}"),
+            }
+        }
+        Self { validators }
+    }
+
+    /// Check a result of the tool `name`.
+    ///
+    /// Error results and tools without an output schema are always valid.
+    pub fn validate(&self, name: &str, result: &CallToolResult) -> Result<(), InvalidToolOutput> {
+        let Some(validator) = self.validators.get(name) else {
+            return Ok(());
+        };
+        if result.is_error == Some(true) {
+            return Ok(());
+        }
+        let Some(structured_content) = &result.structured_content else {
+            return Err(InvalidToolOutput {
+                tool: name.to_string(),
+                violations: Vec::new(),
+            });
+        };
+        validator
+            .validate(&Value::Object(structured_content.clone()))
+            .map_err(|violations| InvalidToolOutput {
+                tool: name.to_string(),
+                violations,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_violations_are_reported_with_pointer() {
+        let schema = crate::model::object(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z]+$" },
+                "count": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        }));
+        let validator = SchemaValidator::new(&schema).unwrap();
+        assert!(
+            validator
+                .validate(&json!({ "name": "abc", "count": 2 }))
+                .is_ok()
+        );
+
+        let violations = validator
+            .validate(&json!({ "name": "ABC", "count": 0, "extra": true }))
+            .unwrap_err();
+        let pointers: Vec<_> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert!(pointers.contains(&"/name"));
+        assert!(pointers.contains(&"/count"));
+        assert!(pointers.contains(&""));
+
+        let error = violations_error("invalid tool arguments", violations);
+        assert_eq!(error.code, crate::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(
+            error.data.unwrap()["violations"].as_array().unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_tool_output_validator() {
+        let tool = Tool::new("weather", "get the weather", JsonObject::new()).with_output_schema(
+            crate::model::object(json!({
+                "type": "object",
+                "properties": { "temperature": { "type": "number" } },
+                "required": ["temperature"]
+            })),
+        );
+        let validator = ToolOutputValidator::new([&tool]);
+        let valid = CallToolResult::structured(crate::model::object(json!({ "temperature": 1.5 })));
+        assert!(validator.validate("weather", &valid).is_ok());
+        let invalid =
+            CallToolResult::structured(crate::model::object(json!({ "temperature": "hot" })));
+        let error = validator.validate("weather", &invalid).unwrap_err();
+        assert_eq!(error.tool, "weather");
+        assert_eq!(error.violations[0].pointer, "/temperature");
+        let error = validator
+            .validate("weather", &CallToolResult::success(vec![]))
+            .unwrap_err();
+        assert!(error.violations.is_empty());
+        assert!(
+            validator
+                .validate("weather", &CallToolResult::error(vec![]))
+                .is_ok()
+        );
+        assert!(validator.validate("other", &invalid).is_ok());
+    }
+}
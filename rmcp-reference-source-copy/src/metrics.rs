@@ -0,0 +1,526 @@
+//! Metrics of the service loop and the transports
+//!
+//! The service loop, the byte-stream, websocket, SSE server and streamable http server
+//! transports, and the sessions of the
+//! [`LocalSessionManager`](crate::transport::streamable_http_server::session::local::LocalSessionManager)
+//! record into [`Metrics::global`], which can be rendered in the Prometheus text format:
+//!
+//! | name | type | labels |
+//! |------|------|--------|
+//! | `rmcp_requests_total` | counter | `role`, `method` |
+//! | `rmcp_request_errors_total` | counter | `role`, `method`, `code` |
+//! | `rmcp_request_duration_seconds` | histogram | `role`, `method` |
+//! | `rmcp_requests_in_flight` | gauge | `role`, `method` |
+//! | `rmcp_messages_total` | counter | `role`, `direction` |
+//! | `rmcp_transport_bytes_total` | counter | `transport`, `direction` |
+//! | `rmcp_sessions_active` | gauge | |
+//! | `rmcp_sessions_total` | counter | |
+//!
+//! With the `transport-streamable-http-server` feature, [`PrometheusService`] serves them over
+//! http, and can be routed beside a
+//! [`StreamableHttpService`](crate::transport::StreamableHttpService).
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    sync::{
+        Arc, OnceLock, RwLock,
+        atomic::{AtomicI64, AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use crate::{model::ErrorCode, service::ServiceRole, transport::Direction};
+
+const REQUESTS_TOTAL: &str = "rmcp_requests_total";
+const REQUEST_ERRORS_TOTAL: &str = "rmcp_request_errors_total";
+const REQUEST_DURATION_SECONDS: &str = "rmcp_request_duration_seconds";
+const REQUESTS_IN_FLIGHT: &str = "rmcp_requests_in_flight";
+const MESSAGES_TOTAL: &str = "rmcp_messages_total";
+const TRANSPORT_BYTES_TOTAL: &str = "rmcp_transport_bytes_total";
+const SESSIONS_ACTIVE: &str = "rmcp_sessions_active";
+const SESSIONS_TOTAL: &str = "rmcp_sessions_total";
+
+fn help(name: &str) -> &'static str {
+    match name {
+        REQUESTS_TOTAL => "Requests handled, by the role of the service and the method",
+        REQUEST_ERRORS_TOTAL => "Requests answered with an error, by error code",
+        REQUEST_DURATION_SECONDS => "Time from the reception of a request to its response",
+        REQUESTS_IN_FLIGHT => "Requests received and not answered yet",
+        MESSAGES_TOTAL => "JSON-RPC messages received and sent by the service loop",
+        TRANSPORT_BYTES_TOTAL => {
+            "Bytes of the JSON-RPC messages read and written by the transports"
+        }
+        SESSIONS_ACTIVE => "Streamable http sessions open",
+        SESSIONS_TOTAL => "Streamable http sessions created",
+        _ => "",
+    }
+}
+
+/// The upper bounds of the request duration buckets, in seconds
+const DURATION_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+const DIRECTIONS: [Direction; 2] = [Direction::Received, Direction::Sent];
+
+/// The role of the service which records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Role {
+    Client,
+    Server,
+}
+
+impl Role {
+    const ALL: [Role; 2] = [Role::Client, Role::Server];
+
+    pub(crate) fn of<R: ServiceRole>() -> Self {
+        if R::IS_CLIENT {
+            Role::Client
+        } else {
+            Role::Server
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Client => "client",
+            Role::Server => "server",
+        }
+    }
+}
+
+/// The transports which count the bytes of the messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transport {
+    AsyncRw,
+    StreamableHttp,
+    Sse,
+    WebSocket,
+}
+
+impl Transport {
+    const ALL: [Transport; 4] = [
+        Transport::AsyncRw,
+        Transport::StreamableHttp,
+        Transport::Sse,
+        Transport::WebSocket,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Transport::AsyncRw => "async_rw",
+            Transport::StreamableHttp => "streamable_http",
+            Transport::Sse => "sse",
+            Transport::WebSocket => "websocket",
+        }
+    }
+}
+
+/// The series of a metric by their labels, created on first use.
+///
+/// The lock is only taken for writing when a series is created, the series are updated with
+/// atomics.
+#[derive(Debug)]
+struct Family<K, V> {
+    series: RwLock<BTreeMap<K, Arc<V>>>,
+}
+
+impl<K, V> Default for Family<K, V> {
+    fn default() -> Self {
+        Self {
+            series: RwLock::default(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Default> Family<K, V> {
+    fn get(&self, key: K) -> Arc<V> {
+        let series = self
+            .series
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(value) = series.get(&key) {
+            return value.clone();
+        }
+        drop(series);
+        self.series
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(key)
+            .or_default()
+            .clone()
+    }
+
+    fn snapshot(&self) -> Vec<(K, Arc<V>)> {
+        self.series
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// The metrics of the requests of a method
+#[derive(Debug, Default)]
+struct RequestSeries {
+    total: AtomicU64,
+    in_flight: AtomicI64,
+    /// Not cumulative, the last one counts what's above the last bound
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    duration_nanos: AtomicU64,
+    errors: Family<i32, AtomicU64>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: Family<(Role, &'static str), RequestSeries>,
+    /// By role and direction
+    messages: [[AtomicU64; DIRECTIONS.len()]; Role::ALL.len()],
+    /// By transport and direction
+    transport_bytes: [[AtomicU64; DIRECTIONS.len()]; Transport::ALL.len()],
+    sessions_active: AtomicI64,
+    sessions_total: AtomicU64,
+}
+
+/// A set of metrics, cheap to clone
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Registry>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The metrics recorded by this crate
+    pub fn global() -> &'static Metrics {
+        static GLOBAL: OnceLock<Metrics> = OnceLock::new();
+        GLOBAL.get_or_init(Metrics::new)
+    }
+
+    /// Count a request and its duration when the returned timer is dropped
+    pub(crate) fn request_started(&self, role: Role, method: &'static str) -> RequestTimer {
+        let series = self.registry.requests.get((role, method));
+        series.in_flight.fetch_add(1, Ordering::Relaxed);
+        RequestTimer {
+            series,
+            start: Instant::now(),
+            error: None,
+        }
+    }
+
+    pub(crate) fn record_message(&self, role: Role, direction: Direction) {
+        self.registry.messages[role as usize][direction as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "transport-async-rw"), allow(dead_code))]
+    pub(crate) fn record_transport_bytes(
+        &self,
+        transport: Transport,
+        direction: Direction,
+        bytes: usize,
+    ) {
+        self.registry.transport_bytes[transport as usize][direction as usize]
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a session as active until the returned guard is dropped
+    #[cfg_attr(
+        not(feature = "transport-streamable-http-server-session"),
+        allow(dead_code)
+    )]
+    pub(crate) fn session_opened(&self) -> ActiveSession {
+        self.registry.sessions_total.fetch_add(1, Ordering::Relaxed);
+        self.registry
+            .sessions_active
+            .fetch_add(1, Ordering::Relaxed);
+        ActiveSession {
+            metrics: self.clone(),
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let registry = &self.registry;
+        let requests = registry.requests.snapshot();
+        let mut output = Exposition::default();
+
+        output.header(REQUESTS_TOTAL, "counter");
+        for ((role, method), series) in &requests {
+            let labels = [("role", role.as_str()), ("method", *method)];
+            output.sample(
+                REQUESTS_TOTAL,
+                &labels,
+                series.total.load(Ordering::Relaxed),
+            );
+        }
+        output.header(REQUEST_ERRORS_TOTAL, "counter");
+        for ((role, method), series) in &requests {
+            for (code, count) in series.errors.snapshot() {
+                let code = code.to_string();
+                let labels = [
+                    ("role", role.as_str()),
+                    ("method", *method),
+                    ("code", code.as_str()),
+                ];
+                output.sample(REQUEST_ERRORS_TOTAL, &labels, count.load(Ordering::Relaxed));
+            }
+        }
+        output.header(REQUEST_DURATION_SECONDS, "histogram");
+        for ((role, method), series) in &requests {
+            let labels = [("role", role.as_str()), ("method", *method)];
+            let bucket = format!("{REQUEST_DURATION_SECONDS}_bucket");
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(&series.buckets) {
+                cumulative += count.load(Ordering::Relaxed);
+                let le = bound.to_string();
+                let labels = [labels[0], labels[1], ("le", le.as_str())];
+                output.sample(&bucket, &labels, cumulative);
+            }
+            // from the buckets rather than the total, which is updated apart
+            let count = cumulative + series.buckets[DURATION_BUCKETS.len()].load(Ordering::Relaxed);
+            let labels_inf = [labels[0], labels[1], ("le", "+Inf")];
+            output.sample(&bucket, &labels_inf, count);
+            let sum = series.duration_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+            output.sample(&format!("{REQUEST_DURATION_SECONDS}_sum"), &labels, sum);
+            output.sample(&format!("{REQUEST_DURATION_SECONDS}_count"), &labels, count);
+        }
+        output.header(REQUESTS_IN_FLIGHT, "gauge");
+        for ((role, method), series) in &requests {
+            let labels = [("role", role.as_str()), ("method", *method)];
+            let in_flight = series.in_flight.load(Ordering::Relaxed);
+            output.sample(REQUESTS_IN_FLIGHT, &labels, in_flight);
+        }
+
+        output.header(MESSAGES_TOTAL, "counter");
+        for role in Role::ALL {
+            for direction in DIRECTIONS {
+                let labels = [("role", role.as_str()), ("direction", direction.as_str())];
+                let count = &registry.messages[role as usize][direction as usize];
+                output.sample(MESSAGES_TOTAL, &labels, count.load(Ordering::Relaxed));
+            }
+        }
+        output.header(TRANSPORT_BYTES_TOTAL, "counter");
+        for transport in Transport::ALL {
+            for direction in DIRECTIONS {
+                let labels = [
+                    ("transport", transport.as_str()),
+                    ("direction", direction.as_str()),
+                ];
+                let bytes = &registry.transport_bytes[transport as usize][direction as usize];
+                output.sample(
+                    TRANSPORT_BYTES_TOTAL,
+                    &labels,
+                    bytes.load(Ordering::Relaxed),
+                );
+            }
+        }
+
+        output.header(SESSIONS_ACTIVE, "gauge");
+        let active = registry.sessions_active.load(Ordering::Relaxed);
+        output.sample(SESSIONS_ACTIVE, &[], active);
+        output.header(SESSIONS_TOTAL, "counter");
+        let total = registry.sessions_total.load(Ordering::Relaxed);
+        output.sample(SESSIONS_TOTAL, &[], total);
+        output.text
+    }
+}
+
+/// The text of the Prometheus exposition format
+#[derive(Debug, Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    fn header(&mut self, name: &str, kind: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {}", help(name));
+        let _ = writeln!(self.text, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        let _ = writeln!(self.text, "{name}{} {value}", format_labels(labels));
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    let mut formatted = String::new();
+    for (name, value) in labels {
+        formatted.push(if formatted.is_empty() { '{' } else { ',' });
+        let value = value
+            .replace('\\', r"\\")
+            .replace('"', r#"\""#)
+            .replace('\n', r"\n");
+        let _ = write!(formatted, "{name}=\"{value}\"");
+    }
+    if !formatted.is_empty() {
+        formatted.push('}');
+    }
+    formatted
+}
+
+/// Record a request once it's answered, or given up when the timer is dropped early
+#[derive(Debug)]
+pub(crate) struct RequestTimer {
+    series: Arc<RequestSeries>,
+    start: Instant,
+    error: Option<ErrorCode>,
+}
+
+impl RequestTimer {
+    pub(crate) fn record_error(&mut self, code: ErrorCode) {
+        self.error = Some(code);
+    }
+}
+
+impl Drop for RequestTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let series = &self.series;
+        let bucket = DURATION_BUCKETS.partition_point(|bound| *bound < elapsed.as_secs_f64());
+        series.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        series.duration_nanos.fetch_add(
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+        series.total.fetch_add(1, Ordering::Relaxed);
+        series.in_flight.fetch_sub(1, Ordering::Relaxed);
+        if let Some(code) = self.error {
+            series.errors.get(code.0).fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Counts a session as active until it's dropped
+#[derive(Debug)]
+#[cfg_attr(
+    not(feature = "transport-streamable-http-server-session"),
+    allow(dead_code)
+)]
+pub(crate) struct ActiveSession {
+    metrics: Metrics,
+}
+
+impl Drop for ActiveSession {
+    fn drop(&mut self) {
+        self.metrics
+            .registry
+            .sessions_active
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "transport-streamable-http-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-server")))]
+mod prometheus_service {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use futures::future::BoxFuture;
+    use http::{Request, Response, header::CONTENT_TYPE};
+    use http_body_util::{BodyExt, Full, combinators::BoxBody};
+
+    use super::Metrics;
+
+    const PROMETHEUS_MIME_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+    /// Serve the metrics in the Prometheus text format, to whatever request
+    #[derive(Debug, Clone)]
+    pub struct PrometheusService {
+        metrics: Metrics,
+    }
+
+    impl Default for PrometheusService {
+        fn default() -> Self {
+            Self::new(Metrics::global().clone())
+        }
+    }
+
+    impl PrometheusService {
+        pub fn new(metrics: Metrics) -> Self {
+            Self { metrics }
+        }
+    }
+
+    impl<B> tower_service::Service<Request<B>> for PrometheusService {
+        type Response = Response<BoxBody<Bytes, Infallible>>;
+        type Error = Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(
+            &mut self,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<(), Self::Error>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<B>) -> Self::Future {
+            let body = Full::new(Bytes::from(self.metrics.render_prometheus())).boxed();
+            let response = Response::builder()
+                .header(CONTENT_TYPE, PROMETHEUS_MIME_TYPE)
+                .body(body)
+                .expect("valid response");
+            Box::pin(std::future::ready(Ok(response)))
+        }
+    }
+}
+#[cfg(feature = "transport-streamable-http-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-server")))]
+pub use prometheus_service::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus() {
+        let metrics = Metrics::new();
+        {
+            let _ok = metrics.request_started(Role::Server, "tools/call");
+            let mut failed = metrics.request_started(Role::Server, "tools/call");
+            failed.record_error(ErrorCode::INVALID_PARAMS);
+            let _pending = metrics.request_started(Role::Server, "tools/list");
+            assert!(
+                metrics
+                    .render_prometheus()
+                    .contains("rmcp_requests_in_flight{role=\"server\",method=\"tools/list\"} 1")
+            );
+        }
+        metrics.record_message(Role::Server, Direction::Received);
+        metrics.record_transport_bytes(Transport::AsyncRw, Direction::Sent, 42);
+        let _active = metrics.session_opened();
+        drop(metrics.session_opened());
+
+        let rendered = metrics.render_prometheus();
+        for line in [
+            "# TYPE rmcp_requests_total counter",
+            "rmcp_requests_total{role=\"server\",method=\"tools/call\"} 2",
+            "rmcp_request_errors_total{role=\"server\",method=\"tools/call\",code=\"-32602\"} 1",
+            "rmcp_requests_in_flight{role=\"server\",method=\"tools/list\"} 0",
+            "rmcp_request_duration_seconds_bucket{role=\"server\",method=\"tools/call\",le=\"+Inf\"} 2",
+            "rmcp_request_duration_seconds_count{role=\"server\",method=\"tools/call\"} 2",
+            "rmcp_messages_total{role=\"server\",direction=\"received\"} 1",
+            "rmcp_transport_bytes_total{transport=\"async_rw\",direction=\"sent\"} 42",
+            "rmcp_transport_bytes_total{transport=\"websocket\",direction=\"received\"} 0",
+            "rmcp_sessions_active 1",
+            "rmcp_sessions_total 2",
+        ] {
+            assert!(
+                rendered.lines().any(|rendered| rendered == line),
+                "missing {line} in\n{rendered}"
+            );
+        }
+        assert_eq!(rendered.matches("# TYPE rmcp_requests_total").count(), 1);
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(
+            format_labels(&[("method", "a\"b\\c\nd")]),
+            r#"{method="a\"b\\c\nd"}"#
+        );
+        assert_eq!(format_labels(&[]), "");
+    }
+}
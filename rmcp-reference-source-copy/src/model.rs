@@ -58,6 +58,9 @@ pub struct EmptyObject {}
 
 pub trait ConstString: Default {
     const VALUE: &str;
+    fn as_str(&self) -> &'static str {
+        Self::VALUE
+    }
 }
 #[macro_export]
 macro_rules! const_string {
@@ -143,9 +146,10 @@ impl std::fmt::Display for ProtocolVersion {
 }
 
 impl ProtocolVersion {
+    pub const V_2025_06_18: Self = Self(Cow::Borrowed("2025-06-18"));
     pub const V_2025_03_26: Self = Self(Cow::Borrowed("2025-03-26"));
     pub const V_2024_11_05: Self = Self(Cow::Borrowed("2024-11-05"));
-    pub const LATEST: Self = Self::V_2025_03_26;
+    pub const LATEST: Self = Self::V_2025_06_18;
 }
 
 impl Serialize for ProtocolVersion {
@@ -167,6 +171,7 @@ impl<'de> Deserialize<'de> for ProtocolVersion {
         match s.as_str() {
             "2024-11-05" => return Ok(ProtocolVersion::V_2024_11_05),
             "2025-03-26" => return Ok(ProtocolVersion::V_2025_03_26),
+            "2025-06-18" => return Ok(ProtocolVersion::V_2025_06_18),
             _ => {}
         }
         Ok(ProtocolVersion(Cow::Owned(s)))
@@ -426,7 +431,9 @@ pub struct JsonRpcNotification<N = Notification> {
 pub struct ErrorCode(pub i32);
 
 impl ErrorCode {
+    pub const REQUEST_TIMEOUT: Self = Self(-32001);
     pub const RESOURCE_NOT_FOUND: Self = Self(-32002);
+    pub const TOO_MANY_REQUESTS: Self = Self(-32003);
     pub const INVALID_REQUEST: Self = Self(-32600);
     pub const METHOD_NOT_FOUND: Self = Self(-32601);
     pub const INVALID_PARAMS: Self = Self(-32602);
@@ -483,6 +490,12 @@ impl ErrorData {
     pub fn internal_error(message: impl Into<Cow<'static, str>>, data: Option<Value>) -> Self {
         Self::new(ErrorCode::INTERNAL_ERROR, message, data)
     }
+    pub fn request_timeout(message: impl Into<Cow<'static, str>>, data: Option<Value>) -> Self {
+        Self::new(ErrorCode::REQUEST_TIMEOUT, message, data)
+    }
+    pub fn too_many_requests(message: impl Into<Cow<'static, str>>, data: Option<Value>) -> Self {
+        Self::new(ErrorCode::TOO_MANY_REQUESTS, message, data)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -511,6 +524,12 @@ pub enum JsonRpcBatchResponseItem<Resp> {
 }
 
 impl<Resp> JsonRpcBatchResponseItem<Resp> {
+    pub fn id(&self) -> &RequestId {
+        match self {
+            JsonRpcBatchResponseItem::Response(r) => &r.id,
+            JsonRpcBatchResponseItem::Error(e) => &e.id,
+        }
+    }
     pub fn into_non_batch_message<Req, Not>(self) -> JsonRpcMessage<Req, Resp, Not> {
         match self {
             JsonRpcBatchResponseItem::Response(r) => JsonRpcMessage::Response(r),
@@ -660,7 +679,7 @@ pub type InitializedNotification = NotificationNoParam<InitializedNotificationMe
 ///
 /// This contains the client's protocol version, capabilities, and implementation
 /// information, allowing the server to understand what the client supports.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct InitializeRequestParam {
@@ -676,7 +695,7 @@ pub struct InitializeRequestParam {
 ///
 /// Contains the server's protocol version, capabilities, and implementation
 /// information, along with optional instructions for the client.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct InitializeResult {
@@ -694,27 +713,6 @@ pub struct InitializeResult {
 pub type ServerInfo = InitializeResult;
 pub type ClientInfo = InitializeRequestParam;
 
-impl Default for ServerInfo {
-    fn default() -> Self {
-        ServerInfo {
-            protocol_version: ProtocolVersion::default(),
-            capabilities: ServerCapabilities::default(),
-            server_info: Implementation::from_build_env(),
-            instructions: None,
-        }
-    }
-}
-
-impl Default for ClientInfo {
-    fn default() -> Self {
-        ClientInfo {
-            protocol_version: ProtocolVersion::default(),
-            capabilities: ClientCapabilities::default(),
-            client_info: Implementation::from_build_env(),
-        }
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Implementation {
@@ -919,8 +917,8 @@ pub type ToolListChangedNotification = NotificationNoParam<ToolListChangedNotifi
 // LOGGING
 // =============================================================================
 
-/// Logging levels supported by the MCP protocol
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Copy)]
+/// Logging levels supported by the MCP protocol, ordered by severity
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Copy)]
 #[serde(rename_all = "lowercase")] //match spec
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum LoggingLevel {
@@ -1085,6 +1083,75 @@ pub struct ModelHint {
     pub name: Option<String>,
 }
 
+// =============================================================================
+// ELICITATION (USER INPUT)
+// =============================================================================
+
+const_string!(CreateElicitationRequestMethod = "elicitation/create");
+/// Request from the server asking the client to collect input from the user
+pub type CreateElicitationRequest =
+    Request<CreateElicitationRequestMethod, CreateElicitationRequestParam>;
+
+/// Parameters for requesting input from the user.
+///
+/// The requested schema is restricted to a flat object whose properties are
+/// primitive values (string, number, integer, boolean or enum).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateElicitationRequestParam {
+    /// The message to present to the user
+    pub message: String,
+    /// A JSON Schema object describing the expected shape of the response
+    pub requested_schema: JsonObject,
+}
+
+/// The action taken by the user in response to an elicitation request
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ElicitationAction {
+    /// The user submitted the requested data
+    Accept,
+    /// The user explicitly declined to provide the data
+    Decline,
+    /// The user dismissed the request without making a choice
+    Cancel,
+}
+
+/// The result of an elicitation request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateElicitationResult {
+    /// The action taken by the user
+    pub action: ElicitationAction,
+    /// The submitted data, only present when the action is [`ElicitationAction::Accept`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<JsonObject>,
+}
+
+impl CreateElicitationResult {
+    pub fn accept(content: JsonObject) -> Self {
+        Self {
+            action: ElicitationAction::Accept,
+            content: Some(content),
+        }
+    }
+    pub fn decline() -> Self {
+        Self {
+            action: ElicitationAction::Decline,
+            content: None,
+        }
+    }
+    pub fn cancel() -> Self {
+        Self {
+            action: ElicitationAction::Cancel,
+            content: None,
+        }
+    }
+}
+
 // =============================================================================
 // COMPLETION AND AUTOCOMPLETE
 // =============================================================================
@@ -1179,14 +1246,17 @@ pub type RootsListChangedNotification = NotificationNoParam<RootsListChangedNoti
 
 /// The result of a tool call operation.
 ///
-/// Contains the content returned by the tool execution and an optional
-/// flag indicating whether the operation resulted in an error.
+/// Contains the content returned by the tool execution, an optional structured
+/// result and an optional flag indicating whether the operation resulted in an error.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CallToolResult {
     /// The content returned by the tool (text, images, etc.)
     pub content: Vec<Content>,
+    /// A structured result, which should conform to the tool's output schema if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<JsonObject>,
     /// Whether this result represents an error condition
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
@@ -1197,6 +1267,7 @@ impl CallToolResult {
     pub fn success(content: Vec<Content>) -> Self {
         CallToolResult {
             content,
+            structured_content: None,
             is_error: Some(false),
         }
     }
@@ -1204,6 +1275,26 @@ impl CallToolResult {
     pub fn error(content: Vec<Content>) -> Self {
         CallToolResult {
             content,
+            structured_content: None,
+            is_error: Some(true),
+        }
+    }
+    /// Create a successful tool result with structured content.
+    ///
+    /// The serialized object is also added as a text content, for clients which don't
+    /// support structured content.
+    pub fn structured(value: JsonObject) -> Self {
+        CallToolResult {
+            content: vec![Content::text(Value::Object(value.clone()).to_string())],
+            structured_content: Some(value),
+            is_error: Some(false),
+        }
+    }
+    /// Create an error tool result with structured content
+    pub fn structured_error(value: JsonObject) -> Self {
+        CallToolResult {
+            content: vec![Content::text(Value::Object(value.clone()).to_string())],
+            structured_content: Some(value),
             is_error: Some(true),
         }
     }
@@ -1315,7 +1406,11 @@ ts_union!(
 );
 
 ts_union!(
-    export type ClientResult = CreateMessageResult | ListRootsResult | EmptyResult;
+    export type ClientResult =
+    | CreateMessageResult
+    | ListRootsResult
+    | CreateElicitationResult
+    | EmptyResult;
 );
 
 impl ClientResult {
@@ -1330,7 +1425,8 @@ ts_union!(
     export type ServerRequest =
     | PingRequest
     | CreateMessageRequest
-    | ListRootsRequest;
+    | ListRootsRequest
+    | CreateElicitationRequest;
 );
 
 ts_union!(
@@ -1400,6 +1496,39 @@ impl From<CancelledNotification> for ClientNotification {
     }
 }
 
+impl TryInto<ProgressNotification> for ServerNotification {
+    type Error = ServerNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ServerNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl TryInto<ProgressNotification> for ClientNotification {
+    type Error = ClientNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ClientNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+impl From<ProgressNotification> for ServerNotification {
+    fn from(value: ProgressNotification) -> Self {
+        ServerNotification::ProgressNotification(value)
+    }
+}
+
+impl From<ProgressNotification> for ClientNotification {
+    fn from(value: ProgressNotification) -> Self {
+        ClientNotification::ProgressNotification(value)
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -1560,6 +1689,71 @@ mod tests {
     fn test_protocol_version_order() {
         let v1 = ProtocolVersion::V_2024_11_05;
         let v2 = ProtocolVersion::V_2025_03_26;
+        let v3 = ProtocolVersion::V_2025_06_18;
         assert!(v1 < v2);
+        assert!(v2 < v3);
+        assert_eq!(ProtocolVersion::LATEST, v3);
+    }
+
+    #[test]
+    fn test_elicitation_serde() {
+        let raw = json!({
+            "jsonrpc": JsonRpcVersion2_0,
+            "id": 1,
+            "method": "elicitation/create",
+            "params": {
+                "message": "Confirm the deletion",
+                "requestedSchema": {
+                    "type": "object",
+                    "properties": { "confirm": { "type": "boolean" } },
+                    "required": ["confirm"]
+                }
+            }
+        });
+        let message: ServerJsonRpcMessage = serde_json::from_value(raw.clone()).unwrap();
+        match &message {
+            ServerJsonRpcMessage::Request(JsonRpcRequest {
+                request: ServerRequest::CreateElicitationRequest(request),
+                ..
+            }) => {
+                assert_eq!(request.params.message, "Confirm the deletion");
+                assert_eq!(request.params.requested_schema["type"], "object");
+            }
+            other => panic!("Expected CreateElicitationRequest, got {other:?}"),
+        }
+        assert_eq!(serde_json::to_value(&message).unwrap(), raw);
+
+        let raw = json!({
+            "jsonrpc": JsonRpcVersion2_0,
+            "id": 1,
+            "result": { "action": "accept", "content": { "confirm": true } }
+        });
+        let message: ClientJsonRpcMessage = serde_json::from_value(raw).unwrap();
+        match message {
+            ClientJsonRpcMessage::Response(JsonRpcResponse {
+                result: ClientResult::CreateElicitationResult(result),
+                ..
+            }) => {
+                assert_eq!(result.action, ElicitationAction::Accept);
+                assert_eq!(result.content, Some(object(json!({ "confirm": true }))));
+            }
+            other => panic!("Expected CreateElicitationResult, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_structured_call_tool_result() {
+        let structured = object(json!({ "temperature": 22.5 }));
+        let result = CallToolResult::structured(structured.clone());
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["structuredContent"], json!({ "temperature": 22.5 }));
+        assert_eq!(value["isError"], json!(false));
+
+        let result: CallToolResult = serde_json::from_value(value).unwrap();
+        assert_eq!(result.structured_content, Some(structured));
+
+        let result = CallToolResult::success(vec![]);
+        let value = serde_json::to_value(&result).unwrap();
+        assert!(value.get("structuredContent").is_none());
     }
 }
@@ -58,6 +58,12 @@ impl<T: AnnotateAble> DerefMut for Annotated<T> {
     }
 }
 
+impl<T: AnnotateAble> From<T> for Annotated<T> {
+    fn from(raw: T) -> Self {
+        Annotated::new(raw, None)
+    }
+}
+
 impl<T: AnnotateAble> Annotated<T> {
     pub fn new(raw: T, annotations: Option<Annotations>) -> Self {
         Self { raw, annotations }
@@ -47,6 +47,7 @@ pub struct RootsCapabilities {
 ///     .enable_experimental()
 ///     .enable_roots()
 ///     .enable_roots_list_changed()
+///     .enable_elicitation()
 ///     .build();
 /// ```
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
@@ -58,6 +59,8 @@ pub struct ClientCapabilities {
     pub roots: Option<RootsCapabilities>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<JsonObject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<JsonObject>,
 }
 
 ///
@@ -252,11 +255,12 @@ builder! {
         experimental: ExperimentalCapabilities,
         roots: RootsCapabilities,
         sampling: JsonObject,
+        elicitation: JsonObject,
     }
 }
 
-impl<const E: bool, const S: bool>
-    ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<E, true, S>>
+impl<const E: bool, const S: bool, const EL: bool>
+    ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<E, true, S, EL>>
 {
     pub fn enable_roots_list_changed(mut self) -> Self {
         if let Some(c) = self.roots.as_mut() {
@@ -295,7 +299,8 @@ mod test {
             .enable_experimental()
             .enable_roots()
             .enable_roots_list_changed()
-            .enable_sampling();
+            .enable_sampling()
+            .enable_elicitation();
         assert_eq!(
             client_builder.experimental,
             Some(ExperimentalCapabilities::default())
@@ -306,5 +311,6 @@ mod test {
                 list_changed: Some(true),
             })
         );
+        assert_eq!(client_builder.elicitation, Some(JsonObject::default()));
     }
 }
@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
-    ClientNotification, ClientRequest, Extensions, JsonObject, JsonRpcMessage, NumberOrString,
-    ProgressToken, ServerNotification, ServerRequest,
+    ClientNotification, ClientRequest, ConstString, Extensions, JsonObject, JsonRpcMessage,
+    NumberOrString, ProgressToken, ServerNotification, ServerRequest,
 };
 
 pub trait GetMeta {
@@ -18,6 +18,10 @@ pub trait GetExtensions {
     fn extensions_mut(&mut self) -> &mut Extensions;
 }
 
+pub trait GetMethod {
+    fn method(&self) -> &'static str;
+}
+
 macro_rules! variant_extension {
     (
         $Enum: ident {
@@ -40,6 +44,15 @@ macro_rules! variant_extension {
                 }
             }
         }
+        impl GetMethod for $Enum {
+            fn method(&self) -> &'static str {
+                match self {
+                    $(
+                        $Enum::$variant(v) => v.method.as_str(),
+                    )*
+                }
+            }
+        }
         impl GetMeta for $Enum {
             fn get_meta_mut(&mut self) -> &mut Meta {
                 self.extensions_mut().get_or_insert_default()
@@ -74,6 +87,7 @@ variant_extension! {
         PingRequest
         CreateMessageRequest
         ListRootsRequest
+        CreateElicitationRequest
     }
 }
 
@@ -101,6 +115,9 @@ variant_extension! {
 #[serde(transparent)]
 pub struct Meta(pub JsonObject);
 const PROGRESS_TOKEN_FIELD: &str = "progressToken";
+const TIMEOUT_FIELD: &str = "timeoutMs";
+const TRACEPARENT_FIELD: &str = "traceparent";
+const TRACESTATE_FIELD: &str = "tracestate";
 impl Meta {
     pub fn new() -> Self {
         Self(JsonObject::new())
@@ -133,6 +150,44 @@ impl Meta {
         };
     }
 
+    /// The time the sender of a request is willing to wait for its response
+    pub fn get_timeout(&self) -> Option<std::time::Duration> {
+        self.0
+            .get(TIMEOUT_FIELD)
+            .and_then(Value::as_u64)
+            .map(std::time::Duration::from_millis)
+    }
+
+    pub fn set_timeout(&mut self, timeout: std::time::Duration) {
+        let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self.0
+            .insert(TIMEOUT_FIELD.to_string(), Value::from(millis));
+    }
+
+    /// The W3C `traceparent` of the span the message was sent from
+    pub fn get_traceparent(&self) -> Option<&str> {
+        self.0.get(TRACEPARENT_FIELD).and_then(Value::as_str)
+    }
+
+    pub fn set_traceparent(&mut self, traceparent: impl Into<String>) {
+        self.0.insert(
+            TRACEPARENT_FIELD.to_string(),
+            Value::String(traceparent.into()),
+        );
+    }
+
+    /// The W3C `tracestate`, vendor specific data going with the `traceparent`
+    pub fn get_tracestate(&self) -> Option<&str> {
+        self.0.get(TRACESTATE_FIELD).and_then(Value::as_str)
+    }
+
+    pub fn set_tracestate(&mut self, tracestate: impl Into<String>) {
+        self.0.insert(
+            TRACESTATE_FIELD.to_string(),
+            Value::String(tracestate.into()),
+        );
+    }
+
     pub fn extend(&mut self, other: Meta) {
         for (k, v) in other.0.into_iter() {
             self.0.insert(k, v);
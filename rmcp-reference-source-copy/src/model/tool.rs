@@ -19,6 +19,9 @@ pub struct Tool {
     pub description: Option<Cow<'static, str>>,
     /// A JSON Schema object defining the expected parameters for the tool
     pub input_schema: Arc<JsonObject>,
+    /// An optional JSON Schema object defining the structure of the tool's structured output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Arc<JsonObject>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Optional additional tool information.
     pub annotations: Option<ToolAnnotations>,
@@ -136,10 +139,22 @@ impl Tool {
             name: name.into(),
             description: Some(description.into()),
             input_schema: input_schema.into(),
+            output_schema: None,
             annotations: None,
         }
     }
 
+    /// Set the schema of the structured content returned by this tool
+    pub fn with_output_schema<S>(self, output_schema: S) -> Self
+    where
+        S: Into<Arc<JsonObject>>,
+    {
+        Tool {
+            output_schema: Some(output_schema.into()),
+            ..self
+        }
+    }
+
     pub fn annotate(self, annotations: ToolAnnotations) -> Self {
         Tool {
             annotations: Some(annotations),
@@ -151,4 +166,11 @@ impl Tool {
     pub fn schema_as_json_value(&self) -> Value {
         Value::Object(self.input_schema.as_ref().clone())
     }
+
+    /// Get the output schema as json value
+    pub fn output_schema_as_json_value(&self) -> Option<Value> {
+        self.output_schema
+            .as_ref()
+            .map(|schema| Value::Object(schema.as_ref().clone()))
+    }
 }
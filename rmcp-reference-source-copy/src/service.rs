@@ -5,12 +5,17 @@ use crate::{
     error::ErrorData as McpError,
     model::{
         CancelledNotification, CancelledNotificationParam, Extensions, GetExtensions, GetMeta,
-        JsonRpcBatchRequestItem, JsonRpcBatchResponseItem, JsonRpcError, JsonRpcMessage,
-        JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, Meta, NumberOrString, ProgressToken,
-        RequestId, ServerJsonRpcMessage,
+        GetMethod, JsonRpcBatchRequestItem, JsonRpcBatchResponseItem, JsonRpcError, JsonRpcMessage,
+        JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, JsonRpcVersion2_0, Meta,
+        NumberOrString, ProgressNotification, ProgressToken, ProtocolVersion, RequestId,
     },
     transport::{DynamicTransportError, IntoTransport, Transport},
 };
+mod concurrency;
+pub use concurrency::*;
+pub mod middleware;
+mod timeout;
+pub use timeout::*;
 #[cfg(feature = "client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
 mod client;
@@ -23,14 +28,19 @@ mod server;
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
 pub use server::*;
+#[cfg(all(test, feature = "server"))]
+pub(crate) mod test_util;
 #[cfg(feature = "tower")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
 mod tower;
+#[cfg(feature = "otel")]
+#[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+mod trace_context;
 use tokio_util::sync::{CancellationToken, DropGuard};
 #[cfg(feature = "tower")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
 pub use tower::*;
-use tracing::instrument;
+use tracing::{Instrument, instrument};
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum ServiceError {
@@ -46,6 +56,10 @@ pub enum ServiceError {
     Cancelled { reason: Option<String> },
     #[error("request timeout after {}", chrono::Duration::from_std(*timeout).unwrap_or_default())]
     Timeout { timeout: Duration },
+    #[error(transparent)]
+    InvalidToolOutput(#[from] crate::handler::validation::InvalidToolOutput),
+    #[error("json-rpc batches are not supported by protocol version {0}")]
+    BatchNotSupported(ProtocolVersion),
 }
 
 trait TransferObject:
@@ -71,10 +85,12 @@ pub trait ServiceRole: std::fmt::Debug + Send + Sync + 'static + Copy + Clone {
     type Not: TryInto<CancelledNotification, Error = Self::Not>
         + From<CancelledNotification>
         + TransferObject;
-    type PeerReq: TransferObject + GetMeta + GetExtensions;
+    type PeerReq: TransferObject + GetMeta + GetExtensions + GetMethod;
     type PeerResp: TransferObject;
     type PeerNot: TryInto<CancelledNotification, Error = Self::PeerNot>
         + From<CancelledNotification>
+        + TryInto<ProgressNotification, Error = Self::PeerNot>
+        + From<ProgressNotification>
         + TransferObject
         + GetMeta
         + GetExtensions;
@@ -82,6 +98,18 @@ pub trait ServiceRole: std::fmt::Debug + Send + Sync + 'static + Copy + Clone {
     const IS_CLIENT: bool;
     type Info: TransferObject;
     type PeerInfo: TransferObject;
+    /// The protocol version agreed on at initialization, `None` if it can't be told
+    fn negotiated_protocol_version(
+        _info: &Self::Info,
+        _peer_info: &Self::PeerInfo,
+    ) -> Option<ProtocolVersion> {
+        None
+    }
+}
+
+/// Json-rpc batches were removed by `2025-06-18`
+fn supports_batch(protocol_version: &ProtocolVersion) -> bool {
+    *protocol_version < ProtocolVersion::V_2025_06_18
 }
 
 pub type TxJsonRpcMessage<R> =
@@ -113,6 +141,23 @@ pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
     fn into_dyn(self) -> Box<dyn DynService<R>> {
         Box::new(self)
     }
+    /// Wrap this service with a middleware, the last one added sees the requests first
+    fn with_middleware<M>(self, middleware: M) -> middleware::WithMiddleware<M, Self>
+    where
+        M: middleware::Middleware<R>,
+    {
+        middleware::WithMiddleware::new(middleware, self)
+    }
+    /// Pass the requests to this service through a tower layer, such as a timeout or a
+    /// concurrency limit
+    #[cfg(feature = "tower")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+    fn with_tower_layer<L>(self, layer: L) -> middleware::TowerLayered<L::Service, Self>
+    where
+        L: tower_layer::Layer<middleware::McpTowerService<Self>>,
+    {
+        middleware::TowerLayered::new(layer, self)
+    }
     fn serve<T, E, A>(
         self,
         transport: T,
@@ -129,6 +174,32 @@ pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
         transport: T,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<R, Self>, R::InitializeError>> + Send
+    where
+        T: IntoTransport<R, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+        Self: Sized,
+    {
+        Self::serve_with_config_and_ct(self, transport, ServiceConfig::default(), ct)
+    }
+    /// Serve with a [`ServiceConfig`], for example to limit the concurrent requests
+    fn serve_with_config<T, E, A>(
+        self,
+        transport: T,
+        config: ServiceConfig,
+    ) -> impl Future<Output = Result<RunningService<R, Self>, R::InitializeError>> + Send
+    where
+        T: IntoTransport<R, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+        Self: Sized,
+    {
+        Self::serve_with_config_and_ct(self, transport, config, Default::default())
+    }
+    fn serve_with_config_and_ct<T, E, A>(
+        self,
+        transport: T,
+        config: ServiceConfig,
+        ct: CancellationToken,
+    ) -> impl Future<Output = Result<RunningService<R, Self>, R::InitializeError>> + Send
     where
         T: IntoTransport<R, E, A>,
         E: std::error::Error + Send + Sync + 'static,
@@ -162,12 +233,12 @@ pub trait DynService<R: ServiceRole>: Send + Sync {
         &self,
         request: R::PeerReq,
         context: RequestContext<R>,
-    ) -> BoxFuture<Result<R::Resp, McpError>>;
+    ) -> BoxFuture<'_, Result<R::Resp, McpError>>;
     fn handle_notification(
         &self,
         notification: R::PeerNot,
         context: NotificationContext<R>,
-    ) -> BoxFuture<Result<(), McpError>>;
+    ) -> BoxFuture<'_, Result<(), McpError>>;
     fn get_info(&self) -> R::Info;
 }
 
@@ -176,14 +247,14 @@ impl<R: ServiceRole, S: Service<R>> DynService<R> for S {
         &self,
         request: R::PeerReq,
         context: RequestContext<R>,
-    ) -> BoxFuture<Result<R::Resp, McpError>> {
+    ) -> BoxFuture<'_, Result<R::Resp, McpError>> {
         Box::pin(self.handle_request(request, context))
     }
     fn handle_notification(
         &self,
         notification: R::PeerNot,
         context: NotificationContext<R>,
-    ) -> BoxFuture<Result<(), McpError>> {
+    ) -> BoxFuture<'_, Result<(), McpError>> {
         Box::pin(self.handle_notification(notification, context))
     }
     fn get_info(&self) -> R::Info {
@@ -237,6 +308,10 @@ type Responder<T> = tokio::sync::oneshot::Sender<T>;
 /// You can cancel it by call [`RequestHandle::cancel`] with a reason,
 ///
 /// or wait for response by call [`RequestHandle::await_response`]
+///
+/// If it's dropped before the response is received, the request is cancelled with the reason
+/// [`RequestHandle::REQUEST_DROPPED_REASON`], unless the request is sent with
+/// [`PeerRequestOptions::detach_on_drop`].
 #[derive(Debug)]
 pub struct RequestHandle<R: ServiceRole> {
     pub rx: tokio::sync::oneshot::Receiver<Result<R::PeerResp, ServiceError>>,
@@ -244,12 +319,59 @@ pub struct RequestHandle<R: ServiceRole> {
     pub peer: Peer<R>,
     pub id: RequestId,
     pub progress_token: ProgressToken,
+    cancel_on_drop: CancelOnDrop<R>,
+}
+
+/// Send `notifications/cancelled` for a request when dropped, unless disarmed
+#[derive(Debug)]
+struct CancelOnDrop<R: ServiceRole> {
+    peer: Option<Peer<R>>,
+    id: RequestId,
+}
+
+impl<R: ServiceRole> CancelOnDrop<R> {
+    fn new(peer: &Peer<R>, id: &RequestId, options: &PeerRequestOptions) -> Self {
+        Self {
+            peer: (!options.detach_on_drop).then(|| peer.clone()),
+            id: id.clone(),
+        }
+    }
+    fn disarm(&mut self) {
+        self.peer = None;
+    }
+}
+
+impl<R: ServiceRole> Drop for CancelOnDrop<R> {
+    fn drop(&mut self) {
+        let Some(peer) = self.peer.take() else {
+            return;
+        };
+        let notification = CancelledNotification {
+            params: CancelledNotificationParam {
+                request_id: self.id.clone(),
+                reason: Some(RequestHandle::<R>::REQUEST_DROPPED_REASON.to_owned()),
+            },
+            method: crate::model::CancelledNotificationMethod,
+            extensions: Default::default(),
+        };
+        if peer.try_send_notification(notification.clone().into()) {
+            return;
+        }
+        // the outbound channel is full, wait for room if there is a runtime to do it
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = peer.send_notification(notification.into()).await;
+            });
+        }
+    }
 }
 
 impl<R: ServiceRole> RequestHandle<R> {
     pub const REQUEST_TIMEOUT_REASON: &str = "request timeout";
+    pub const REQUEST_DROPPED_REASON: &str = "request dropped";
     pub async fn await_response(self) -> Result<R::PeerResp, ServiceError> {
-        if let Some(timeout) = self.options.timeout {
+        let mut cancel_on_drop = self.cancel_on_drop;
+        let response = if let Some(timeout) = self.options.timeout {
             let timeout_result = tokio::time::timeout(timeout, async move {
                 self.rx.await.map_err(|_e| ServiceError::TransportClosed)?
             })
@@ -257,6 +379,7 @@ impl<R: ServiceRole> RequestHandle<R> {
             match timeout_result {
                 Ok(response) => response,
                 Err(_) => {
+                    cancel_on_drop.disarm();
                     let error = Err(ServiceError::Timeout { timeout });
                     // cancel this request
                     let notification = CancelledNotification {
@@ -273,11 +396,14 @@ impl<R: ServiceRole> RequestHandle<R> {
             }
         } else {
             self.rx.await.map_err(|_e| ServiceError::TransportClosed)?
-        }
+        };
+        cancel_on_drop.disarm();
+        response
     }
 
     /// Cancel this request
-    pub async fn cancel(self, reason: Option<String>) -> Result<(), ServiceError> {
+    pub async fn cancel(mut self, reason: Option<String>) -> Result<(), ServiceError> {
+        self.cancel_on_drop.disarm();
         let notification = CancelledNotification {
             params: CancelledNotificationParam {
                 request_id: self.id,
@@ -302,6 +428,14 @@ pub(crate) enum PeerSinkMessage<R: ServiceRole> {
         notification: R::Not,
         responder: Responder<Result<(), ServiceError>>,
     },
+    Batch {
+        #[allow(clippy::type_complexity)]
+        requests: Vec<(
+            R::Req,
+            RequestId,
+            Responder<Result<R::PeerResp, ServiceError>>,
+        )>,
+    },
 }
 
 /// An interface to fetch the remote client or server
@@ -315,6 +449,12 @@ pub struct Peer<R: ServiceRole> {
     request_id_provider: Arc<dyn RequestIdProvider>,
     progress_token_provider: Arc<dyn ProgressTokenProvider>,
     info: Arc<tokio::sync::OnceCell<R::PeerInfo>>,
+    /// the minimum level of the log messages the peer asked for
+    #[cfg(feature = "server")]
+    logging_level: Arc<std::sync::RwLock<Option<crate::model::LoggingLevel>>>,
+    /// the subscribers to the progress of the requests sent to the peer
+    #[cfg(feature = "client")]
+    progress_dispatcher: crate::handler::client::progress::ProgressDispatcher,
 }
 
 impl<R: ServiceRole> std::fmt::Debug for Peer<R> {
@@ -332,12 +472,48 @@ type ProxyOutbound<R> = mpsc::Receiver<PeerSinkMessage<R>>;
 pub struct PeerRequestOptions {
     pub timeout: Option<Duration>,
     pub meta: Option<Meta>,
+    /// Let the request run on the peer when its [`RequestHandle`] is dropped before the
+    /// response, instead of cancelling it. Default is `false`.
+    pub detach_on_drop: bool,
 }
 
 impl PeerRequestOptions {
     pub fn no_options() -> Self {
         Self::default()
     }
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    pub fn with_meta(mut self, meta: Meta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+    /// Let the request run on the peer when its [`RequestHandle`] is dropped before the
+    /// response, instead of cancelling it
+    pub fn detach_on_drop(mut self) -> Self {
+        self.detach_on_drop = true;
+        self
+    }
+}
+
+/// Configuration of the service loop
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ServiceConfig {
+    pub concurrency: ConcurrencyConfig,
+    pub timeouts: TimeoutConfig,
+}
+
+impl ServiceConfig {
+    pub fn with_concurrency(mut self, concurrency: ConcurrencyConfig) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
 }
 
 impl<R: ServiceRole> Peer<R> {
@@ -353,6 +529,10 @@ impl<R: ServiceRole> Peer<R> {
                 request_id_provider,
                 progress_token_provider: Arc::new(AtomicU32ProgressTokenProvider::default()),
                 info: Arc::new(tokio::sync::OnceCell::new_with(peer_info)),
+                #[cfg(feature = "server")]
+                logging_level: Default::default(),
+                #[cfg(feature = "client")]
+                progress_dispatcher: Default::default(),
             },
             rx,
         )
@@ -368,6 +548,18 @@ impl<R: ServiceRole> Peer<R> {
             .map_err(|_m| ServiceError::TransportClosed)?;
         receiver.await.map_err(|_e| ServiceError::TransportClosed)?
     }
+    /// Queue a notification without waiting, it's dropped if the outbound channel is full.
+    ///
+    /// This is meant for sync contexts, returns whether the notification was queued.
+    pub(crate) fn try_send_notification(&self, notification: R::Not) -> bool {
+        let (responder, _receiver) = tokio::sync::oneshot::channel();
+        self.tx
+            .try_send(PeerSinkMessage::Notification {
+                notification,
+                responder,
+            })
+            .is_ok()
+    }
     pub async fn send_request(&self, request: R::Req) -> Result<R::PeerResp, ServiceError> {
         self.send_request_with_option(request, PeerRequestOptions::no_options())
             .await?
@@ -388,14 +580,7 @@ impl<R: ServiceRole> Peer<R> {
         mut request: R::Req,
         options: PeerRequestOptions,
     ) -> Result<RequestHandle<R>, ServiceError> {
-        let id = self.request_id_provider.next_request_id();
-        let progress_token = self.progress_token_provider.next_progress_token();
-        request
-            .get_meta_mut()
-            .set_progress_token(progress_token.clone());
-        if let Some(meta) = options.meta.clone() {
-            request.get_meta_mut().extend(meta);
-        }
+        let (id, progress_token) = self.prepare_request(&mut request, &options);
         let (responder, receiver) = tokio::sync::oneshot::channel();
         self.tx
             .send(PeerSinkMessage::Request {
@@ -406,6 +591,7 @@ impl<R: ServiceRole> Peer<R> {
             .await
             .map_err(|_m| ServiceError::TransportClosed)?;
         Ok(RequestHandle {
+            cancel_on_drop: CancelOnDrop::new(self, &id, &options),
             id,
             rx: receiver,
             progress_token,
@@ -413,6 +599,79 @@ impl<R: ServiceRole> Peer<R> {
             peer: self.clone(),
         })
     }
+
+    /// Send several requests as a single json-rpc batch.
+    ///
+    /// Returns one [`RequestHandle`] per request, in the same order as the requests. Batches were
+    /// removed by protocol version `2025-06-18`, once it has been negotiated every request fails
+    /// with [`ServiceError::BatchNotSupported`].
+    pub async fn send_batch(
+        &self,
+        requests: Vec<R::Req>,
+    ) -> Result<Vec<RequestHandle<R>>, ServiceError> {
+        self.send_batch_with_option(
+            requests
+                .into_iter()
+                .map(|request| (request, PeerRequestOptions::no_options()))
+                .collect(),
+        )
+        .await
+    }
+
+    /// Send several requests as a single json-rpc batch, each with its own options.
+    ///
+    /// Nothing is sent if `requests` is empty.
+    pub async fn send_batch_with_option(
+        &self,
+        requests: Vec<(R::Req, PeerRequestOptions)>,
+    ) -> Result<Vec<RequestHandle<R>>, ServiceError> {
+        let mut batch = Vec::with_capacity(requests.len());
+        let mut handles = Vec::with_capacity(requests.len());
+        for (mut request, options) in requests {
+            let (id, progress_token) = self.prepare_request(&mut request, &options);
+            let (responder, receiver) = tokio::sync::oneshot::channel();
+            batch.push((request, id.clone(), responder));
+            handles.push(RequestHandle {
+                cancel_on_drop: CancelOnDrop::new(self, &id, &options),
+                id,
+                rx: receiver,
+                progress_token,
+                options,
+                peer: self.clone(),
+            });
+        }
+        if batch.is_empty() {
+            return Ok(handles);
+        }
+        self.tx
+            .send(PeerSinkMessage::Batch { requests: batch })
+            .await
+            .map_err(|_m| ServiceError::TransportClosed)?;
+        Ok(handles)
+    }
+
+    fn prepare_request(
+        &self,
+        request: &mut R::Req,
+        options: &PeerRequestOptions,
+    ) -> (RequestId, ProgressToken) {
+        let id = self.request_id_provider.next_request_id();
+        let progress_token = self.progress_token_provider.next_progress_token();
+        request
+            .get_meta_mut()
+            .set_progress_token(progress_token.clone());
+        if let Some(timeout) = options.timeout {
+            // let the peer know when we'll give up
+            request.get_meta_mut().set_timeout(timeout);
+        }
+        if let Some(meta) = options.meta.clone() {
+            request.get_meta_mut().extend(meta);
+        }
+        #[cfg(feature = "otel")]
+        trace_context::inject_current_context(request.get_meta_mut());
+        (id, progress_token)
+    }
+
     pub fn peer_info(&self) -> Option<&R::PeerInfo> {
         self.info.get()
     }
@@ -428,6 +687,14 @@ impl<R: ServiceRole> Peer<R> {
     pub fn is_transport_closed(&self) -> bool {
         self.tx.is_closed()
     }
+    /// Wait until the transport of this peer is closed
+    pub async fn transport_closed(&self) {
+        self.tx.closed().await
+    }
+    /// Whether both handles are for the same connection
+    pub fn is_same_peer(&self, other: &Self) -> bool {
+        self.tx.same_channel(&other.tx)
+    }
 }
 
 #[derive(Debug)]
@@ -436,6 +703,7 @@ pub struct RunningService<R: ServiceRole, S: Service<R>> {
     peer: Peer<R>,
     handle: tokio::task::JoinHandle<QuitReason>,
     cancellation_token: CancellationToken,
+    concurrency_stats: ConcurrencyStats,
     dg: DropGuard,
 }
 impl<R: ServiceRole, S: Service<R>> Deref for RunningService<R, S> {
@@ -455,6 +723,11 @@ impl<R: ServiceRole, S: Service<R>> RunningService<R, S> {
     pub fn service(&self) -> &S {
         self.service.as_ref()
     }
+    /// Live counts of the requests from the peer being handled or waiting for a slot
+    #[inline]
+    pub fn concurrency_stats(&self) -> ConcurrencyStats {
+        self.concurrency_stats.clone()
+    }
     #[inline]
     pub fn cancellation_token(&self) -> RunningServiceCancellationToken {
         RunningServiceCancellationToken(self.cancellation_token.clone())
@@ -498,6 +771,23 @@ pub struct RequestContext<R: ServiceRole> {
     pub peer: Peer<R>,
 }
 
+impl<R: ServiceRole> RequestContext<R> {
+    /// When the handling of this request times out, see [`TimeoutConfig`].
+    ///
+    /// `ct` is cancelled once it's passed. The deadline is kept as a [`RequestDeadline`] in
+    /// the extensions.
+    pub fn deadline(&self) -> Option<tokio::time::Instant> {
+        self.extensions
+            .get::<RequestDeadline>()
+            .map(|deadline| deadline.0)
+    }
+    /// The time left before the deadline, `None` if there is no deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline()
+            .map(|deadline| deadline.saturating_duration_since(tokio::time::Instant::now()))
+    }
+}
+
 /// Request execution context
 #[derive(Debug, Clone)]
 pub struct NotificationContext<R: ServiceRole> {
@@ -529,6 +819,23 @@ pub fn serve_directly_with_ct<R, S, T, E, A>(
     peer_info: Option<R::PeerInfo>,
     ct: CancellationToken,
 ) -> RunningService<R, S>
+where
+    R: ServiceRole,
+    S: Service<R>,
+    T: IntoTransport<R, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    serve_directly_with_config_and_ct(service, transport, peer_info, ServiceConfig::default(), ct)
+}
+
+/// Use this function to skip initialization process
+pub fn serve_directly_with_config_and_ct<R, S, T, E, A>(
+    service: S,
+    transport: T,
+    peer_info: Option<R::PeerInfo>,
+    config: ServiceConfig,
+    ct: CancellationToken,
+) -> RunningService<R, S>
 where
     R: ServiceRole,
     S: Service<R>,
@@ -536,7 +843,124 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     let (peer, peer_rx) = Peer::new(Arc::new(AtomicU32RequestIdProvider::default()), peer_info);
-    serve_inner(service, transport.into_transport(), peer, peer_rx, ct)
+    serve_inner(
+        service,
+        transport.into_transport(),
+        peer,
+        peer_rx,
+        config,
+        ct,
+    )
+}
+
+/// Count a message received or sent by the service loop
+#[inline]
+fn record_message<R: ServiceRole>(_sent: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let direction = if _sent {
+            crate::transport::Direction::Sent
+        } else {
+            crate::transport::Direction::Received
+        };
+        let role = crate::metrics::Role::of::<R>();
+        crate::metrics::Metrics::global().record_message(role, direction);
+    }
+}
+
+/// Handle a request from the peer, resolving to the response that should be sent back
+fn handle_peer_request<R, S>(
+    service: Arc<S>,
+    peer: Peer<R>,
+    id: RequestId,
+    mut request: R::PeerReq,
+    limiter: &ConcurrencyLimiter,
+    timeouts: &TimeoutConfig,
+    ct: CancellationToken,
+) -> impl Future<Output = JsonRpcBatchResponseItem<R::Resp>> + Send + 'static
+where
+    R: ServiceRole,
+    S: Service<R>,
+{
+    tracing::debug!(%id, ?request, "received request");
+    let method = request.method();
+    let deadline = timeouts.deadline(method, request.get_meta());
+    let admission = limiter.admit(method, ct.clone());
+    #[cfg(feature = "metrics")]
+    let mut timer =
+        crate::metrics::Metrics::global().request_started(crate::metrics::Role::of::<R>(), method);
+    // before the meta is moved into the context
+    #[cfg(feature = "otel")]
+    let span = trace_context::request_span::<R>(&request, &id);
+    #[cfg(not(feature = "otel"))]
+    let span = tracing::Span::none();
+    let mut extensions = Extensions::new();
+    let mut meta = Meta::new();
+    // avoid clone
+    // swap meta firstly, otherwise progress token will be lost
+    std::mem::swap(&mut meta, request.get_meta_mut());
+    std::mem::swap(&mut extensions, request.extensions_mut());
+    if let Some(deadline) = deadline {
+        extensions.insert(RequestDeadline(deadline));
+    }
+    let context = RequestContext {
+        ct: ct.clone(),
+        id: id.clone(),
+        peer,
+        meta,
+        extensions,
+    };
+    async move {
+        let handle = async {
+            match admission?.await {
+                Some(_permit) => service.handle_request(request, context).await,
+                None => Err(McpError::internal_error(
+                    "request cancelled before it started",
+                    None,
+                )),
+            }
+        };
+        let result = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, handle).await {
+                Ok(result) => result,
+                Err(_) => {
+                    ct.cancel();
+                    Err(McpError::request_timeout(
+                        "request handling timed out",
+                        Some(serde_json::json!({ "method": method })),
+                    ))
+                }
+            },
+            None => handle.await,
+        };
+        #[cfg(feature = "metrics")]
+        if let Err(error) = &result {
+            timer.record_error(error.code);
+        }
+        #[cfg(feature = "otel")]
+        if let Err(error) = &result {
+            trace_context::record_error(error);
+        }
+        match result {
+            Ok(result) => {
+                tracing::debug!(%id, ?result, "response message");
+                JsonRpcBatchResponseItem::Response(JsonRpcResponse {
+                    jsonrpc: JsonRpcVersion2_0,
+                    id,
+                    result,
+                })
+            }
+            Err(error) => {
+                tracing::warn!(%id, ?error, "response error");
+                JsonRpcBatchResponseItem::Error(JsonRpcError {
+                    jsonrpc: JsonRpcVersion2_0,
+                    id,
+                    error,
+                })
+            }
+        }
+    }
+    .instrument(span)
 }
 
 #[instrument(skip_all)]
@@ -545,6 +969,7 @@ fn serve_inner<R, S, T>(
     transport: T,
     peer: Peer<R>,
     mut peer_rx: tokio::sync::mpsc::Receiver<PeerSinkMessage<R>>,
+    config: ServiceConfig,
     ct: CancellationToken,
 ) -> RunningService<R, S>
 where
@@ -561,6 +986,10 @@ where
     } else {
         tracing::info!(?peer_info, "Service initialized as server");
     }
+    // batches are accepted unless a version without them has been negotiated
+    let batch_rejected_by = peer_info
+        .and_then(|peer_info| R::negotiated_protocol_version(&service.get_info(), peer_info))
+        .filter(|version| !supports_batch(version));
 
     let mut local_responder_pool =
         HashMap::<RequestId, Responder<Result<R::PeerResp, ServiceError>>>::new();
@@ -573,6 +1002,8 @@ where
     // let mut stream = std::pin::pin!(stream);
     let serve_loop_ct = ct.child_token();
     let peer_return: Peer<R> = peer.clone();
+    let limiter = ConcurrencyLimiter::new(config.concurrency.clone());
+    let concurrency_stats = limiter.stats();
     let handle = tokio::spawn(async move {
         let mut transport = transport.into_transport();
         let mut batch_messages = VecDeque::<RxJsonRpcMessage<R>>::new();
@@ -583,6 +1014,10 @@ where
                 id: RequestId,
                 result: Result<(), DynamicTransportError>,
             },
+            Batch {
+                ids: Vec<RequestId>,
+                result: Result<(), DynamicTransportError>,
+            },
             Notification {
                 responder: Responder<Result<(), ServiceError>>,
                 cancellation_param: Option<CancelledNotificationParam>,
@@ -611,6 +1046,7 @@ where
                     }
                     m = transport.receive() => {
                         if let Some(m) = m {
+                            record_message::<R>(false);
                             Event::PeerMessage(m)
                         } else {
                             // input stream closed
@@ -650,9 +1086,28 @@ where
             tracing::trace!(?evt, "new event");
             match evt {
                 Event::SendTaskResult(SendTaskResult::Request { id, result }) => {
+                    if let Err(e) = result
+                        && let Some(responder) = local_responder_pool.remove(&id)
+                    {
+                        let _ = responder.send(Err(ServiceError::TransportSend(e)));
+                    }
+                }
+                Event::SendTaskResult(SendTaskResult::Batch { ids, result }) => {
                     if let Err(e) = result {
-                        if let Some(responder) = local_responder_pool.remove(&id) {
-                            let _ = responder.send(Err(ServiceError::TransportSend(e)));
+                        let mut responders =
+                            ids.iter().filter_map(|id| local_responder_pool.remove(id));
+                        if let Some(first) = responders.next() {
+                            // the error can't be cloned, the other requests get a copy of its message
+                            for responder in responders {
+                                let _ = responder.send(Err(ServiceError::TransportSend(
+                                    DynamicTransportError {
+                                        transport_name: e.transport_name.clone(),
+                                        transport_type_id: e.transport_type_id,
+                                        error: e.error.to_string().into(),
+                                    },
+                                )));
+                            }
+                            let _ = first.send(Err(ServiceError::TransportSend(e)));
                         }
                     }
                 }
@@ -667,25 +1122,32 @@ where
                         Ok(())
                     };
                     let _ = responder.send(response);
-                    if let Some(param) = cancellation_param {
-                        if let Some(responder) = local_responder_pool.remove(&param.request_id) {
-                            tracing::info!(id = %param.request_id, reason = param.reason, "cancelled");
-                            let _response_result = responder.send(Err(ServiceError::Cancelled {
-                                reason: param.reason.clone(),
-                            }));
-                        }
+                    if let Some(param) = cancellation_param
+                        && let Some(responder) = local_responder_pool.remove(&param.request_id)
+                    {
+                        tracing::info!(id = %param.request_id, reason = param.reason, "cancelled");
+                        let _response_result = responder.send(Err(ServiceError::Cancelled {
+                            reason: param.reason.clone(),
+                        }));
                     }
                 }
                 // response and error
                 Event::ToSink(m) => {
-                    if let Some(id) = match &m {
-                        JsonRpcMessage::Response(response) => Some(&response.id),
-                        JsonRpcMessage::Error(error) => Some(&error.id),
-                        _ => None,
-                    } {
-                        if let Some(ct) = local_ct_pool.remove(id) {
-                            ct.cancel();
+                    let ids: Vec<&RequestId> = match &m {
+                        JsonRpcMessage::Response(response) => vec![&response.id],
+                        JsonRpcMessage::Error(error) => vec![&error.id],
+                        JsonRpcMessage::BatchResponse(batch) => {
+                            batch.iter().map(JsonRpcBatchResponseItem::id).collect()
                         }
+                        _ => vec![],
+                    };
+                    if !ids.is_empty() {
+                        for id in ids {
+                            if let Some(ct) = local_ct_pool.remove(id) {
+                                ct.cancel();
+                            }
+                        }
+                        record_message::<R>(true);
                         let send = transport.send(m);
                         tokio::spawn(async move {
                             let send_result = send.await;
@@ -701,6 +1163,7 @@ where
                     responder,
                 }) => {
                     local_responder_pool.insert(id.clone(), responder);
+                    record_message::<R>(true);
                     let send = transport.send(JsonRpcMessage::request(request, id.clone()));
                     {
                         let id = id.clone();
@@ -710,6 +1173,32 @@ where
                         }));
                     }
                 }
+                Event::ProxyMessage(PeerSinkMessage::Batch { requests }) => {
+                    if let Some(version) = &batch_rejected_by {
+                        for (_request, _id, responder) in requests {
+                            let _ = responder
+                                .send(Err(ServiceError::BatchNotSupported(version.clone())));
+                        }
+                        continue;
+                    }
+                    let mut ids = Vec::with_capacity(requests.len());
+                    let mut batch = Vec::with_capacity(requests.len());
+                    for (request, id, responder) in requests {
+                        local_responder_pool.insert(id.clone(), responder);
+                        ids.push(id.clone());
+                        batch.push(JsonRpcBatchRequestItem::Request(JsonRpcRequest {
+                            jsonrpc: JsonRpcVersion2_0,
+                            id,
+                            request,
+                        }));
+                    }
+                    record_message::<R>(true);
+                    let send = transport.send(JsonRpcMessage::BatchRequest(batch));
+                    send_task_set.spawn(send.map(move |r| SendTaskResult::Batch {
+                        ids,
+                        result: r.map_err(DynamicTransportError::new::<T, R>),
+                    }));
+                }
                 Event::ProxyMessage(PeerSinkMessage::Notification {
                     notification,
                     responder,
@@ -723,6 +1212,7 @@ where
                         }
                         Err(notification) => notification,
                     };
+                    record_message::<R>(true);
                     let send = transport.send(JsonRpcMessage::notification(notification));
                     send_task_set.spawn(send.map(move |result| SendTaskResult::Notification {
                         responder,
@@ -731,45 +1221,25 @@ where
                     }));
                 }
                 Event::PeerMessage(JsonRpcMessage::Request(JsonRpcRequest {
-                    id,
-                    mut request,
-                    ..
+                    id, request, ..
                 })) => {
-                    tracing::debug!(%id, ?request, "received request");
-                    {
-                        let service = shared_service.clone();
-                        let sink = sink_proxy_tx.clone();
-                        let request_ct = serve_loop_ct.child_token();
-                        let context_ct = request_ct.child_token();
-                        local_ct_pool.insert(id.clone(), request_ct);
-                        let mut extensions = Extensions::new();
-                        let mut meta = Meta::new();
-                        // avoid clone
-                        // swap meta firstly, otherwise progress token will be lost
-                        std::mem::swap(&mut meta, request.get_meta_mut());
-                        std::mem::swap(&mut extensions, request.extensions_mut());
-                        let context = RequestContext {
-                            ct: context_ct,
-                            id: id.clone(),
-                            peer: peer.clone(),
-                            meta,
-                            extensions,
-                        };
-                        tokio::spawn(async move {
-                            let result = service.handle_request(request, context).await;
-                            let response = match result {
-                                Ok(result) => {
-                                    tracing::debug!(%id, ?result, "response message");
-                                    JsonRpcMessage::response(result, id)
-                                }
-                                Err(error) => {
-                                    tracing::warn!(%id, ?error, "response error");
-                                    JsonRpcMessage::error(error, id)
-                                }
-                            };
-                            let _send_result = sink.send(response).await;
-                        });
-                    }
+                    let request_ct = serve_loop_ct.child_token();
+                    let context_ct = request_ct.child_token();
+                    local_ct_pool.insert(id.clone(), request_ct);
+                    let response = handle_peer_request(
+                        shared_service.clone(),
+                        peer.clone(),
+                        id,
+                        request,
+                        &limiter,
+                        &config.timeouts,
+                        context_ct,
+                    );
+                    let sink = sink_proxy_tx.clone();
+                    tokio::spawn(async move {
+                        let response = response.await.into_non_batch_message();
+                        let _send_result = sink.send(response).await;
+                    });
                 }
                 Event::PeerMessage(JsonRpcMessage::Notification(JsonRpcNotification {
                     notification,
@@ -787,6 +1257,18 @@ where
                         }
                         Err(notification) => notification,
                     };
+                    // feed the progress streams of the pending requests
+                    #[cfg(feature = "client")]
+                    {
+                        notification = match notification.try_into() {
+                            Ok::<ProgressNotification, _>(progress) => {
+                                peer.progress_dispatcher
+                                    .try_handle_notification(progress.params.clone());
+                                progress.into()
+                            }
+                            Err(notification) => notification,
+                        };
+                    }
                     {
                         let service = shared_service.clone();
                         let mut extensions = Extensions::new();
@@ -827,12 +1309,71 @@ where
                         }
                     }
                 }
+                // the requests in a batch are answered with a single batch response,
+                // the notifications are handled as if they were sent alone
                 Event::PeerMessage(JsonRpcMessage::BatchRequest(batch)) => {
-                    batch_messages.extend(
-                        batch
+                    if let Some(version) = &batch_rejected_by {
+                        let error = McpError::invalid_request(
+                            ServiceError::BatchNotSupported(version.clone()).to_string(),
+                            None,
+                        );
+                        tracing::warn!(%error, "reject json-rpc batch");
+                        let responses = batch
                             .into_iter()
-                            .map(JsonRpcBatchRequestItem::into_non_batch_message),
-                    );
+                            .filter_map(|item| match item {
+                                JsonRpcBatchRequestItem::Request(request) => {
+                                    Some(JsonRpcBatchResponseItem::Error(JsonRpcError {
+                                        jsonrpc: JsonRpcVersion2_0,
+                                        id: request.id,
+                                        error: error.clone(),
+                                    }))
+                                }
+                                JsonRpcBatchRequestItem::Notification(_) => None,
+                            })
+                            .collect::<Vec<_>>();
+                        if !responses.is_empty() {
+                            let sink = sink_proxy_tx.clone();
+                            tokio::spawn(async move {
+                                let _send_result =
+                                    sink.send(JsonRpcMessage::BatchResponse(responses)).await;
+                            });
+                        }
+                        continue;
+                    }
+                    let mut responses = Vec::new();
+                    for item in batch {
+                        match item {
+                            JsonRpcBatchRequestItem::Request(JsonRpcRequest {
+                                id,
+                                request,
+                                ..
+                            }) => {
+                                let request_ct = serve_loop_ct.child_token();
+                                let context_ct = request_ct.child_token();
+                                local_ct_pool.insert(id.clone(), request_ct);
+                                responses.push(handle_peer_request(
+                                    shared_service.clone(),
+                                    peer.clone(),
+                                    id,
+                                    request,
+                                    &limiter,
+                                    &config.timeouts,
+                                    context_ct,
+                                ));
+                            }
+                            JsonRpcBatchRequestItem::Notification(notification) => {
+                                batch_messages.push_back(JsonRpcMessage::Notification(notification))
+                            }
+                        }
+                    }
+                    if !responses.is_empty() {
+                        let sink = sink_proxy_tx.clone();
+                        tokio::spawn(async move {
+                            let responses = futures::future::join_all(responses).await;
+                            let _send_result =
+                                sink.send(JsonRpcMessage::BatchResponse(responses)).await;
+                        });
+                    }
                 }
                 Event::PeerMessage(JsonRpcMessage::BatchResponse(batch)) => {
                     batch_messages.extend(
@@ -855,6 +1396,340 @@ where
         peer: peer_return,
         handle,
         cancellation_token: ct.clone(),
+        concurrency_stats,
         dg: ct.drop_guard(),
     }
 }
+
+#[cfg(all(
+    test,
+    feature = "client",
+    feature = "server",
+    feature = "transport-async-rw"
+))]
+mod tests {
+    use super::*;
+    use crate::{ServerHandler, service::test_util::ping};
+
+    struct PingServer;
+    impl ServerHandler for PingServer {}
+
+    #[tokio::test]
+    async fn test_batch_request_gets_single_batch_response() {
+        let (client, server) = tokio::io::duplex(4096);
+        let _server = serve_directly::<RoleServer, _, _, _, _>(PingServer, server, None);
+        let mut client = IntoTransport::<RoleClient, _, _>::into_transport(client);
+
+        let batch = [1, 2]
+            .into_iter()
+            .map(|id| {
+                JsonRpcBatchRequestItem::Request(JsonRpcRequest {
+                    jsonrpc: JsonRpcVersion2_0,
+                    id: RequestId::Number(id),
+                    request: ping(),
+                })
+            })
+            .collect();
+        client
+            .send(JsonRpcMessage::BatchRequest(batch))
+            .await
+            .unwrap();
+        let Some(JsonRpcMessage::BatchResponse(responses)) = client.receive().await else {
+            panic!("expect a batch response");
+        };
+        let mut ids: Vec<_> = responses.iter().map(|item| item.id().clone()).collect();
+        ids.sort_by_key(|id| id.to_string());
+        assert_eq!(ids, vec![RequestId::Number(1), RequestId::Number(2)]);
+    }
+
+    struct SlowServer {
+        cancelled: Arc<tokio::sync::Notify>,
+    }
+    impl ServerHandler for SlowServer {
+        async fn ping(&self, context: RequestContext<RoleServer>) -> Result<(), McpError> {
+            assert!(context.remaining().is_some());
+            // the handler future is dropped on timeout, watch the token from another task
+            let cancelled = self.cancelled.clone();
+            tokio::spawn(async move {
+                context.ct.cancelled().await;
+                cancelled.notify_one();
+            });
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_handling_timeout() {
+        let (client, server) = tokio::io::duplex(4096);
+        let cancelled = Arc::new(tokio::sync::Notify::new());
+        let config = ServiceConfig::default().with_timeouts(
+            TimeoutConfig::default().method_timeout("ping", Duration::from_millis(50)),
+        );
+        let _server = serve_directly_with_config_and_ct::<RoleServer, _, _, _, _>(
+            SlowServer {
+                cancelled: cancelled.clone(),
+            },
+            server,
+            None,
+            config,
+            Default::default(),
+        );
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let error = client.send_request(ping()).await.unwrap_err();
+        let ServiceError::McpError(error) = error else {
+            panic!("expect an mcp error, got {error}");
+        };
+        assert_eq!(error.code, crate::model::ErrorCode::REQUEST_TIMEOUT);
+        cancelled.notified().await;
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request_is_cancelled() {
+        let (client, server) = tokio::io::duplex(4096);
+        let cancelled = Arc::new(tokio::sync::Notify::new());
+        let config = ServiceConfig::default()
+            .with_timeouts(TimeoutConfig::default().default_timeout(Duration::from_secs(60)));
+        let _server = serve_directly_with_config_and_ct::<RoleServer, _, _, _, _>(
+            SlowServer {
+                cancelled: cancelled.clone(),
+            },
+            server,
+            None,
+            config,
+            Default::default(),
+        );
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let request = client.send_request(ping());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), request)
+                .await
+                .is_err()
+        );
+        cancelled.notified().await;
+    }
+
+    #[tokio::test]
+    async fn test_detached_request_is_not_cancelled() {
+        let (client, server) = tokio::io::duplex(4096);
+        let cancelled = Arc::new(tokio::sync::Notify::new());
+        let config = ServiceConfig::default()
+            .with_timeouts(TimeoutConfig::default().default_timeout(Duration::from_secs(60)));
+        let _server = serve_directly_with_config_and_ct::<RoleServer, _, _, _, _>(
+            SlowServer {
+                cancelled: cancelled.clone(),
+            },
+            server,
+            None,
+            config,
+            Default::default(),
+        );
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let options = PeerRequestOptions {
+            detach_on_drop: true,
+            ..Default::default()
+        };
+        let handle = client
+            .send_request_with_option(ping(), options)
+            .await
+            .unwrap();
+        drop(handle);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), cancelled.notified())
+                .await
+                .is_err()
+        );
+    }
+
+    struct RootsServer;
+    impl ServerHandler for RootsServer {
+        async fn call_tool(
+            &self,
+            _request: crate::model::CallToolRequestParam,
+            context: RequestContext<RoleServer>,
+        ) -> Result<crate::model::CallToolResult, McpError> {
+            context
+                .peer
+                .list_roots()
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            Ok(crate::model::CallToolResult::success(vec![]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_keeps_receiving_responses() {
+        let (client, server) = tokio::io::duplex(4096);
+        let config = ServiceConfig::default()
+            .with_concurrency(ConcurrencyConfig::default().max_in_flight(1).max_queued(1));
+        let _server = serve_directly_with_config_and_ct::<RoleServer, _, _, _, _>(
+            RootsServer,
+            server,
+            None,
+            config,
+            Default::default(),
+        );
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        // the running call waits for the client while the other one fills the queue
+        let call = || {
+            client.call_tool(crate::model::CallToolRequestParam {
+                name: "roots".into(),
+                arguments: None,
+            })
+        };
+        let (first, second) = tokio::time::timeout(
+            Duration::from_secs(5),
+            futures::future::join(call(), call()),
+        )
+        .await
+        .expect("the calls must not wait for each other");
+        first.unwrap();
+        second.unwrap();
+    }
+
+    struct ProgressServer;
+    impl ServerHandler for ProgressServer {
+        async fn call_tool(
+            &self,
+            _request: crate::model::CallToolRequestParam,
+            context: RequestContext<RoleServer>,
+        ) -> Result<crate::model::CallToolResult, McpError> {
+            let progress_token = context.meta.get_progress_token().expect("progress token");
+            for progress in 1..=2 {
+                context
+                    .peer
+                    .notify_progress(crate::model::ProgressNotificationParam {
+                        progress_token: progress_token.clone(),
+                        progress,
+                        total: Some(2),
+                        message: None,
+                    })
+                    .await
+                    .unwrap();
+            }
+            Ok(crate::model::CallToolResult::success(vec![]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_with_progress() {
+        use futures::StreamExt;
+
+        let (client, server) = tokio::io::duplex(4096);
+        let _server = serve_directly::<RoleServer, _, _, _, _>(ProgressServer, server, None);
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let call = client
+            .call_tool_with_progress(crate::model::CallToolRequestParam {
+                name: "work".into(),
+                arguments: None,
+            })
+            .await
+            .unwrap();
+        // the stream ends with the request, even when the result isn't awaited yet
+        let progress: Vec<_> = call.progress.map(|p| p.progress).collect().await;
+        assert_eq!(progress, [1, 2]);
+        call.result.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_all_tools_paginated() {
+        use crate::handler::server::{
+            pagination::Paginator,
+            router::{Router, tool::ToolRoute},
+        };
+
+        let tools = ["a", "b", "c", "d", "e"].map(|name| {
+            let tool =
+                crate::model::Tool::new(name, name, Arc::new(crate::model::JsonObject::new()));
+            ToolRoute::new_dyn(tool, |_| {
+                Box::pin(async { Ok(crate::model::CallToolResult::success(vec![])) })
+            })
+        });
+        let router = Router::new(PingServer)
+            .with_tools(tools)
+            .with_pagination(Paginator::new(2));
+        let (client, server) = tokio::io::duplex(4096);
+        let _server = serve_directly::<RoleServer, _, _, _, _>(router, server, None);
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let first_page = client.list_tools(None).await.unwrap();
+        assert_eq!(first_page.tools.len(), 2);
+        assert!(first_page.next_cursor.is_some());
+        let names: Vec<_> = client
+            .list_all_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+
+        let error = client
+            .list_tools(Some(crate::model::PaginatedRequestParam {
+                cursor: Some("forged".into()),
+            }))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ServiceError::McpError(error) if error.code == crate::model::ErrorCode::INVALID_PARAMS
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_batch() {
+        let (client, server) = tokio::io::duplex(4096);
+        let _server = serve_directly::<RoleServer, _, _, _, _>(PingServer, server, None);
+        let client = serve_directly::<RoleClient, _, _, _, _>((), client, None);
+
+        let handles = client
+            .send_batch(vec![ping(), ping(), ping()])
+            .await
+            .unwrap();
+        assert_eq!(handles.len(), 3);
+        for handle in handles {
+            handle.await_response().await.unwrap();
+        }
+        assert!(client.send_batch(vec![]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_rejected_after_2025_06_18() {
+        let (client, server) = tokio::io::duplex(4096);
+        let client_info = crate::model::ClientInfo::default();
+        assert_eq!(client_info.protocol_version, ProtocolVersion::V_2025_06_18);
+        let _server =
+            serve_directly::<RoleServer, _, _, _, _>(PingServer, server, Some(client_info));
+        let mut client = IntoTransport::<RoleClient, _, _>::into_transport(client);
+        let batch = vec![JsonRpcBatchRequestItem::Request(JsonRpcRequest {
+            jsonrpc: JsonRpcVersion2_0,
+            id: RequestId::Number(1),
+            request: ping(),
+        })];
+        client
+            .send(JsonRpcMessage::BatchRequest(batch))
+            .await
+            .unwrap();
+        let Some(JsonRpcMessage::BatchResponse(responses)) = client.receive().await else {
+            panic!("expect a batch response");
+        };
+        let [JsonRpcBatchResponseItem::Error(error)] = responses.as_slice() else {
+            panic!("expect a single error, got {responses:?}");
+        };
+        assert_eq!(error.error.code, crate::model::ErrorCode::INVALID_REQUEST);
+
+        let (client, _server) = tokio::io::duplex(4096);
+        let client = serve_directly::<RoleClient, _, _, _, _>(
+            (),
+            client,
+            Some(crate::model::ServerInfo::default()),
+        );
+        let mut handles = client.send_batch(vec![ping()]).await.unwrap();
+        let error = handles.pop().unwrap().await_response().await.unwrap_err();
+        assert!(matches!(error, ServiceError::BatchNotSupported(_)));
+    }
+}
@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 
+use futures::future::BoxFuture;
 use thiserror::Error;
 
 use super::*;
 use crate::{
+    handler::client::progress::ProgressSubscriber,
     model::{
         CallToolRequest, CallToolRequestParam, CallToolResult, CancelledNotification,
         CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage, ClientNotification,
@@ -104,14 +106,22 @@ impl ServiceRole for RoleClient {
     type PeerInfo = ServerInfo;
     type InitializeError = ClientInitializeError;
     const IS_CLIENT: bool = true;
+    fn negotiated_protocol_version(
+        _info: &Self::Info,
+        peer_info: &Self::PeerInfo,
+    ) -> Option<ProtocolVersion> {
+        // the server answers with the version it picked
+        Some(peer_info.protocol_version.clone())
+    }
 }
 
 pub type ServerSink = Peer<RoleClient>;
 
 impl<S: Service<RoleClient>> ServiceExt<RoleClient> for S {
-    fn serve_with_ct<T, E, A>(
+    fn serve_with_config_and_ct<T, E, A>(
         self,
         transport: T,
+        config: ServiceConfig,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<RoleClient, Self>, ClientInitializeError>> + Send
     where
@@ -119,7 +129,7 @@ impl<S: Service<RoleClient>> ServiceExt<RoleClient> for S {
         E: std::error::Error + Send + Sync + 'static,
         Self: Sized,
     {
-        serve_client_with_ct(self, transport, ct)
+        serve_client_with_config_and_ct(self, transport, config, ct)
     }
 }
 
@@ -140,13 +150,27 @@ pub async fn serve_client_with_ct<S, T, E, A>(
     transport: T,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
+where
+    S: Service<RoleClient>,
+    T: IntoTransport<RoleClient, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    serve_client_with_config_and_ct(service, transport, ServiceConfig::default(), ct).await
+}
+
+pub async fn serve_client_with_config_and_ct<S, T, E, A>(
+    service: S,
+    transport: T,
+    config: ServiceConfig,
+    ct: CancellationToken,
+) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
 where
     S: Service<RoleClient>,
     T: IntoTransport<RoleClient, E, A>,
     E: std::error::Error + Send + Sync + 'static,
 {
     tokio::select! {
-        result = serve_client_with_ct_inner(service, transport.into_transport(), ct.clone()) => { result }
+        result = serve_client_with_ct_inner(service, transport.into_transport(), config, ct.clone()) => { result }
         _ = ct.cancelled() => {
             Err(ClientInitializeError::Cancelled)
         }
@@ -156,6 +180,7 @@ where
 async fn serve_client_with_ct_inner<S, T>(
     service: S,
     transport: T,
+    config: ServiceConfig,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleClient, S>, ClientInitializeError>
 where
@@ -207,7 +232,7 @@ where
         ClientInitializeError::transport::<T>(error, "send initialized notification")
     })?;
     let (peer, peer_rx) = Peer::new(id_provider, Some(initialize_result));
-    Ok(serve_inner(service, transport, peer, peer_rx, ct))
+    Ok(serve_inner(service, transport, peer, peer_rx, config, ct))
 }
 
 macro_rules! method {
@@ -312,6 +337,115 @@ impl Peer<RoleClient> {
     method!(peer_not notify_roots_list_changed RootsListChangedNotification);
 }
 
+/// A request sent with a progress token, see [`Peer<RoleClient>::call_tool_with_progress`]
+pub struct ProgressCall<T> {
+    /// The progress notifications of the request, the stream ends once the request is done
+    pub progress: ProgressSubscriber,
+    /// The result of the request, dropping it cancels the request
+    pub result: BoxFuture<'static, Result<T, ServiceError>>,
+}
+
+impl<T> std::fmt::Debug for ProgressCall<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressCall")
+            .field("progress_token", self.progress.progress_token())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Peer<RoleClient> {
+    async fn send_request_with_progress<T: Send + 'static>(
+        &self,
+        request: ClientRequest,
+        expect: fn(ServerResult) -> Option<T>,
+    ) -> Result<ProgressCall<T>, ServiceError> {
+        let progress_token = self.progress_token_provider.next_progress_token();
+        // subscribe before sending, so that no notification is missed
+        let progress = self
+            .progress_dispatcher
+            .subscribe(progress_token.clone())
+            .await;
+        let mut meta = Meta::new();
+        meta.set_progress_token(progress_token.clone());
+        let options = PeerRequestOptions::no_options().with_meta(meta);
+        let handle = self.send_request_with_option(request, options).await?;
+        let dispatcher = self.progress_dispatcher.clone();
+        // wait for the response on its own, so that the progress stream ends even if it's
+        // drained before the result is awaited
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let mut response_tx = response_tx;
+            let response = tokio::select! {
+                response = handle.await_response() => Some(response),
+                // the result was dropped, so is the handle, which cancels the request
+                _ = response_tx.closed() => None,
+            };
+            dispatcher.unsubscribe(&progress_token).await;
+            if let Some(response) = response {
+                let _ = response_tx.send(response);
+            }
+        });
+        let result = Box::pin(async move {
+            let response = response_rx
+                .await
+                .map_err(|_| ServiceError::TransportClosed)?;
+            expect(response?).ok_or(ServiceError::UnexpectedResponse)
+        });
+        Ok(ProgressCall { progress, result })
+    }
+
+    /// Call a tool, and get the progress notifications the server sends for it
+    pub async fn call_tool_with_progress(
+        &self,
+        params: CallToolRequestParam,
+    ) -> Result<ProgressCall<CallToolResult>, ServiceError> {
+        let request = ClientRequest::CallToolRequest(CallToolRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+        self.send_request_with_progress(request, |result| match result {
+            ServerResult::CallToolResult(result) => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Read a resource, and get the progress notifications the server sends for it
+    pub async fn read_resource_with_progress(
+        &self,
+        params: ReadResourceRequestParam,
+    ) -> Result<ProgressCall<ReadResourceResult>, ServiceError> {
+        let request = ClientRequest::ReadResourceRequest(ReadResourceRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+        self.send_request_with_progress(request, |result| match result {
+            ServerResult::ReadResourceResult(result) => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Get a prompt, and get the progress notifications the server sends for it
+    pub async fn get_prompt_with_progress(
+        &self,
+        params: GetPromptRequestParam,
+    ) -> Result<ProgressCall<GetPromptResult>, ServiceError> {
+        let request = ClientRequest::GetPromptRequest(GetPromptRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+        self.send_request_with_progress(request, |result| match result {
+            ServerResult::GetPromptResult(result) => Some(result),
+            _ => None,
+        })
+        .await
+    }
+}
+
 impl Peer<RoleClient> {
     /// A wrapper method for [`Peer<RoleClient>::list_tools`].
     ///
@@ -332,6 +466,22 @@ impl Peer<RoleClient> {
         Ok(tools)
     }
 
+    /// Call a tool, then validate the structured content of the result against the output
+    /// schema of the tool.
+    ///
+    /// A result that doesn't conform is returned as a [`ServiceError::InvalidToolOutput`] listing
+    /// the violations.
+    pub async fn call_tool_validated(
+        &self,
+        params: CallToolRequestParam,
+        validator: &crate::handler::validation::ToolOutputValidator,
+    ) -> Result<CallToolResult, ServiceError> {
+        let name = params.name.clone();
+        let result = self.call_tool(params).await?;
+        validator.validate(&name, &result)?;
+        Ok(result)
+    }
+
     /// A wrapper method for [`Peer<RoleClient>::list_prompts`].
     ///
     /// This function will call [`Peer<RoleClient>::list_prompts`] multiple times until all prompts are listed.
@@ -0,0 +1,268 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use crate::model::ErrorData;
+
+/// What to do with a request that arrives when a concurrency limit is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until a slot is free.
+    ///
+    /// Once [`ConcurrencyConfig::max_queued`] requests are waiting, the next ones are rejected.
+    #[default]
+    Queue,
+    /// Reply right away with a [`TOO_MANY_REQUESTS`](crate::model::ErrorCode::TOO_MANY_REQUESTS) error
+    Reject,
+}
+
+/// Limits on the requests from the peer that are handled at the same time.
+///
+/// No limit is applied by default.
+///
+/// # Example
+/// ```rust
+/// # use rmcp::service::{ConcurrencyConfig, OverflowPolicy};
+/// let config = ConcurrencyConfig::default()
+///     .max_in_flight(64)
+///     .method_limit("tools/call", 8)
+///     .overflow(OverflowPolicy::Reject);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyConfig {
+    /// The maximum number of requests handled at the same time
+    pub max_in_flight: Option<usize>,
+    /// The maximum number of requests handled at the same time, by method
+    pub per_method: HashMap<Cow<'static, str>, usize>,
+    pub overflow: OverflowPolicy,
+    /// The maximum number of waiting requests with [`OverflowPolicy::Queue`], unbounded by default
+    pub max_queued: Option<usize>,
+}
+
+impl ConcurrencyConfig {
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+    pub fn method_limit(mut self, method: impl Into<Cow<'static, str>>, limit: usize) -> Self {
+        self.per_method.insert(method.into(), limit);
+        self
+    }
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+    pub fn max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = Some(max_queued);
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConcurrencyCounters {
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+/// Live counts of the requests from the peer, see [`RunningService::concurrency_stats`](crate::service::RunningService::concurrency_stats)
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyStats {
+    counters: Arc<ConcurrencyCounters>,
+}
+
+impl ConcurrencyStats {
+    /// Requests being handled
+    pub fn in_flight(&self) -> usize {
+        self.counters.in_flight.load(Ordering::Relaxed)
+    }
+    /// Requests waiting for a slot
+    pub fn queue_depth(&self) -> usize {
+        self.counters.queued.load(Ordering::Relaxed)
+    }
+}
+
+/// A slot to handle a request, released when dropped
+#[derive(Debug)]
+pub(crate) struct RequestPermit {
+    _global: Option<OwnedSemaphorePermit>,
+    _method: Option<OwnedSemaphorePermit>,
+    counters: Arc<ConcurrencyCounters>,
+}
+
+impl RequestPermit {
+    fn new(
+        global: Option<OwnedSemaphorePermit>,
+        method: Option<OwnedSemaphorePermit>,
+        counters: Arc<ConcurrencyCounters>,
+    ) -> Self {
+        counters.in_flight.fetch_add(1, Ordering::Relaxed);
+        Self {
+            _global: global,
+            _method: method,
+            counters,
+        }
+    }
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ConcurrencyLimiter {
+    global: Option<Arc<Semaphore>>,
+    per_method: HashMap<Cow<'static, str>, Arc<Semaphore>>,
+    overflow: OverflowPolicy,
+    max_queued: Option<usize>,
+    stats: ConcurrencyStats,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            global: config.max_in_flight.map(|n| Arc::new(Semaphore::new(n))),
+            per_method: config
+                .per_method
+                .into_iter()
+                .map(|(method, n)| (method, Arc::new(Semaphore::new(n))))
+                .collect(),
+            overflow: config.overflow,
+            max_queued: config.max_queued,
+            stats: ConcurrencyStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> ConcurrencyStats {
+        self.stats.clone()
+    }
+
+    fn is_queue_full(&self) -> bool {
+        let queue_depth = self.stats.queue_depth();
+        self.max_queued
+            .is_some_and(|max_queued| queue_depth >= max_queued)
+    }
+
+    /// Admit a request, the returned future resolves to the permit once the request can start.
+    ///
+    /// With [`OverflowPolicy::Reject`], or when the queue is full, a request over the limits is
+    /// rejected right away. Only the service loop admits requests, so the queue can't overflow.
+    pub fn admit(
+        &self,
+        method: &str,
+        ct: CancellationToken,
+    ) -> Result<impl Future<Output = Option<RequestPermit>> + Send + 'static, ErrorData> {
+        let global = self.global.clone();
+        let per_method = self.per_method.get(method).cloned();
+        let counters = self.stats.counters.clone();
+        let try_acquire = |semaphore: &Option<Arc<Semaphore>>| match semaphore {
+            Some(semaphore) => semaphore.clone().try_acquire_owned().map(Some),
+            None => Ok(None),
+        };
+        // take the method slot first, so that a request waiting for it doesn't hold a global slot
+        let immediate = try_acquire(&per_method)
+            .and_then(|method_permit| Ok((try_acquire(&global)?, method_permit)));
+        let permit = match immediate {
+            Ok((global_permit, method_permit)) => {
+                Ok(RequestPermit::new(global_permit, method_permit, counters))
+            }
+            Err(_) if self.overflow == OverflowPolicy::Reject || self.is_queue_full() => {
+                return Err(ErrorData::too_many_requests(
+                    "too many concurrent requests",
+                    Some(serde_json::json!({ "method": method })),
+                ));
+            }
+            Err(_) => {
+                counters.queued.fetch_add(1, Ordering::Relaxed);
+                Err(counters)
+            }
+        };
+        Ok(async move {
+            let counters = match permit {
+                Ok(permit) => return Some(permit),
+                Err(counters) => counters,
+            };
+            let acquire = async {
+                let acquire_one = |semaphore: Option<Arc<Semaphore>>| async move {
+                    match semaphore {
+                        Some(semaphore) => semaphore.acquire_owned().await.ok(),
+                        None => None,
+                    }
+                };
+                let method_permit = acquire_one(per_method).await;
+                let global_permit = acquire_one(global).await;
+                (global_permit, method_permit)
+            };
+            let acquired = tokio::select! {
+                acquired = acquire => Some(acquired),
+                _ = ct.cancelled() => None,
+            };
+            counters.queued.fetch_sub(1, Ordering::Relaxed);
+            acquired.map(|(global_permit, method_permit)| {
+                RequestPermit::new(global_permit, method_permit, counters)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ErrorCode;
+
+    #[tokio::test]
+    async fn test_reject_over_limit() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyConfig::default()
+                .max_in_flight(2)
+                .method_limit("tools/call", 1)
+                .overflow(OverflowPolicy::Reject),
+        );
+        let ct = CancellationToken::new();
+        let call = limiter.admit("tools/call", ct.clone()).unwrap().await;
+        let Err(error) = limiter.admit("tools/call", ct.clone()) else {
+            panic!("expect the request to be rejected");
+        };
+        assert_eq!(error.code, ErrorCode::TOO_MANY_REQUESTS);
+        let ping = limiter.admit("ping", ct.clone()).unwrap().await;
+        assert!(limiter.admit("ping", ct.clone()).is_err());
+        assert_eq!(limiter.stats().in_flight(), 2);
+        drop(call);
+        drop(ping);
+        assert_eq!(limiter.stats().in_flight(), 0);
+        assert!(limiter.admit("tools/call", ct).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queue_until_slot_is_free() {
+        let limiter =
+            ConcurrencyLimiter::new(ConcurrencyConfig::default().max_in_flight(1).max_queued(1));
+        let ct = CancellationToken::new();
+        let first = limiter.admit("ping", ct.clone()).unwrap().await;
+        let second = tokio::spawn(limiter.admit("ping", ct.clone()).unwrap());
+        assert_eq!(limiter.stats().queue_depth(), 1);
+        // the queue is full
+        assert!(limiter.admit("ping", ct.clone()).is_err());
+        drop(first);
+        let second = second.await.unwrap();
+        assert!(second.is_some());
+        assert_eq!(limiter.stats().queue_depth(), 0);
+        assert_eq!(limiter.stats().in_flight(), 1);
+
+        // a cancelled request leaves the queue without a slot
+        let cancelled = CancellationToken::new();
+        let third = limiter.admit("ping", cancelled.clone()).unwrap();
+        cancelled.cancel();
+        assert!(third.await.is_none());
+        assert_eq!(limiter.stats().queue_depth(), 0);
+    }
+}
@@ -0,0 +1,297 @@
+//! Wrap a service with cross-cutting behaviors.
+//!
+//! A [`Middleware`] sees every request and notification before the service it wraps, along
+//! with the full [`RequestContext`], and decides whether and how to pass it on with [`Next`].
+//!
+//! ```rust,ignore
+//! let service = Counter::new()
+//!     .with_middleware(MapError::new(|error| error))
+//!     // the last one added sees the requests first
+//!     .with_middleware(RequestLogging);
+//! ```
+//!
+//! With the `tower` feature, a stock tower layer can wrap a service with
+//! [`ServiceExt::with_tower_layer`](super::ServiceExt::with_tower_layer), and a middleware
+//! can be used as a tower layer with [`MiddlewareLayer`].
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+
+use super::{NotificationContext, RequestContext, Service, ServiceRole};
+use crate::{error::ErrorData as McpError, model::GetMethod};
+
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+mod tower;
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub use tower::*;
+
+pub trait Middleware<R: ServiceRole>: Send + Sync + 'static {
+    fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> BoxFuture<'a, Result<R::Resp, McpError>>;
+
+    /// The notifications are passed on untouched by default
+    fn handle_notification<'a>(
+        &'a self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+        next: Next<'a, R>,
+    ) -> BoxFuture<'a, Result<(), McpError>> {
+        next.handle_notification(notification, context)
+    }
+}
+
+/// What a service, or a tower service, needs to be called from a middleware
+trait NextService<R: ServiceRole>: Send + Sync {
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> BoxFuture<'_, Result<R::Resp, McpError>>;
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> BoxFuture<'_, Result<(), McpError>>;
+}
+
+/// The service under a middleware
+struct ServiceNext<'a, S>(&'a S);
+
+impl<R: ServiceRole, S: Service<R>> NextService<R> for ServiceNext<'_, S> {
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> BoxFuture<'_, Result<R::Resp, McpError>> {
+        Box::pin(Service::handle_request(self.0, request, context))
+    }
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> BoxFuture<'_, Result<(), McpError>> {
+        Box::pin(Service::handle_notification(self.0, notification, context))
+    }
+}
+
+/// The rest of the stack, down to the wrapped service
+pub struct Next<'a, R: ServiceRole> {
+    inner: &'a dyn NextService<R>,
+}
+
+impl<R: ServiceRole> Clone for Next<'_, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R: ServiceRole> Copy for Next<'_, R> {}
+
+impl<R: ServiceRole> std::fmt::Debug for Next<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Next").finish_non_exhaustive()
+    }
+}
+
+impl<'a, R: ServiceRole> Next<'a, R> {
+    pub fn handle_request(
+        self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> BoxFuture<'a, Result<R::Resp, McpError>> {
+        self.inner.handle_request(request, context)
+    }
+
+    pub fn handle_notification(
+        self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> BoxFuture<'a, Result<(), McpError>> {
+        self.inner.handle_notification(notification, context)
+    }
+}
+
+/// A service wrapped with a middleware, see [`ServiceExt::with_middleware`](super::ServiceExt::with_middleware)
+#[derive(Debug, Clone)]
+pub struct WithMiddleware<M, S> {
+    pub middleware: M,
+    pub inner: S,
+}
+
+impl<M, S> WithMiddleware<M, S> {
+    pub fn new(middleware: M, inner: S) -> Self {
+        Self { middleware, inner }
+    }
+}
+
+impl<R, M, S> Service<R> for WithMiddleware<M, S>
+where
+    R: ServiceRole,
+    M: Middleware<R>,
+    S: Service<R>,
+{
+    async fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> Result<R::Resp, McpError> {
+        let next = ServiceNext(&self.inner);
+        self.middleware
+            .handle_request(request, context, Next { inner: &next })
+            .await
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> Result<(), McpError> {
+        let next = ServiceNext(&self.inner);
+        self.middleware
+            .handle_notification(notification, context, Next { inner: &next })
+            .await
+    }
+
+    fn get_info(&self) -> R::Info {
+        self.inner.get_info()
+    }
+}
+
+/// Log the method, id, duration and outcome of each request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestLogging;
+
+impl<R: ServiceRole> Middleware<R> for RequestLogging {
+    fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> BoxFuture<'a, Result<R::Resp, McpError>> {
+        let method = request.method();
+        let id = context.id.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = next.handle_request(request, context).await;
+            let elapsed = start.elapsed();
+            match &result {
+                Ok(_) => tracing::info!(method, %id, ?elapsed, "request handled"),
+                Err(error) => tracing::warn!(
+                    method,
+                    %id,
+                    ?elapsed,
+                    code = error.code.0,
+                    message = %error.message,
+                    "request failed"
+                ),
+            }
+            result
+        })
+    }
+}
+
+/// Map the errors of the requests, for example to hide the details of internal errors
+pub struct MapError<F> {
+    f: F,
+}
+
+impl<F> MapError<F>
+where
+    F: Fn(McpError) -> McpError + Send + Sync + 'static,
+{
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> std::fmt::Debug for MapError<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapError").finish_non_exhaustive()
+    }
+}
+
+impl<R, F> Middleware<R> for MapError<F>
+where
+    R: ServiceRole,
+    F: Fn(McpError) -> McpError + Send + Sync + 'static,
+{
+    fn handle_request<'a>(
+        &'a self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+        next: Next<'a, R>,
+    ) -> BoxFuture<'a, Result<R::Resp, McpError>> {
+        Box::pin(async move { next.handle_request(request, context).await.map_err(&self.f) })
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::{
+        RoleServer, ServerHandler,
+        model::{ClientRequest, ErrorCode, ServerResult},
+        service::{
+            ServiceExt,
+            test_util::{ping, request_context},
+        },
+    };
+
+    struct FailingServer;
+    impl ServerHandler for FailingServer {
+        async fn ping(&self, _context: RequestContext<RoleServer>) -> Result<(), McpError> {
+            Err(McpError::internal_error(
+                "database password is hunter2",
+                None,
+            ))
+        }
+    }
+
+    /// Reject the requests without a `tenant` in the extensions
+    struct RequireTenant;
+    impl Middleware<RoleServer> for RequireTenant {
+        fn handle_request<'a>(
+            &'a self,
+            request: ClientRequest,
+            context: RequestContext<RoleServer>,
+            next: Next<'a, RoleServer>,
+        ) -> BoxFuture<'a, Result<ServerResult, McpError>> {
+            if context.extensions.get::<&str>().is_none() {
+                return Box::pin(async { Err(McpError::invalid_request("no tenant", None)) });
+            }
+            next.handle_request(request, context)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_stack() {
+        let service = FailingServer
+            .with_middleware(MapError::new(|error: McpError| {
+                if error.code == ErrorCode::INTERNAL_ERROR {
+                    McpError::internal_error("internal error", None)
+                } else {
+                    error
+                }
+            }))
+            .with_middleware(RequireTenant)
+            .with_middleware(RequestLogging);
+
+        let error = Service::handle_request(&service, ping(), request_context())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+
+        let mut context = request_context::<RoleServer>();
+        context.extensions.insert("acme");
+        let error = Service::handle_request(&service, ping(), context)
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "internal error");
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    future::poll_fn,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tower_layer::Layer;
+use tower_service::Service as TowerService;
+
+use super::{Middleware, Next, NextService};
+use crate::{
+    error::ErrorData as McpError,
+    service::{NotificationContext, RequestContext, Service, ServiceRole},
+};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A request with its context, as seen by the tower services
+#[derive(Debug)]
+pub struct McpRequest<R: ServiceRole> {
+    pub request: R::PeerReq,
+    pub context: RequestContext<R>,
+}
+
+/// The timeout of [`tower::timeout`] is returned as a request timeout, and the other errors of
+/// a tower middleware as internal errors
+fn into_mcp_error(error: impl Into<BoxError>) -> McpError {
+    let error = match error.into().downcast::<McpError>() {
+        Ok(error) => return *error,
+        Err(error) => error,
+    };
+    if error.is::<tower::timeout::error::Elapsed>() {
+        return McpError::request_timeout("request handling timed out", None);
+    }
+    McpError::internal_error(error.to_string(), None)
+}
+
+/// Call a tower service once it's ready, on a clone so that it can be shared
+async fn call_tower<R, T>(service: &T, request: McpRequest<R>) -> Result<R::Resp, McpError>
+where
+    R: ServiceRole,
+    T: TowerService<McpRequest<R>, Response = R::Resp> + Clone,
+    T::Error: Into<BoxError>,
+{
+    let mut service = service.clone();
+    poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .map_err(into_mcp_error)?;
+    service.call(request).await.map_err(into_mcp_error)
+}
+
+/// An MCP service as a tower service of [`McpRequest`]
+pub struct McpTowerService<S> {
+    inner: Arc<S>,
+}
+
+impl<S> Clone for McpTowerService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S> McpTowerService<S> {
+    pub fn new(inner: Arc<S>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<R: ServiceRole, S: Service<R>> TowerService<McpRequest<R>> for McpTowerService<S> {
+    type Response = R::Resp;
+    type Error = McpError;
+    type Future = BoxFuture<'static, Result<R::Resp, McpError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: McpRequest<R>) -> Self::Future {
+        let inner = self.inner.clone();
+        Box::pin(
+            async move { Service::handle_request(inner.as_ref(), req.request, req.context).await },
+        )
+    }
+}
+
+/// A service whose requests go through a tower layer, see
+/// [`ServiceExt::with_tower_layer`](crate::service::ServiceExt::with_tower_layer).
+///
+/// The notifications don't go through the layer, they are passed to the service directly.
+pub struct TowerLayered<T, S> {
+    tower: T,
+    inner: Arc<S>,
+}
+
+impl<T, S> TowerLayered<T, S> {
+    pub fn new<L>(layer: L, inner: S) -> Self
+    where
+        L: Layer<McpTowerService<S>, Service = T>,
+    {
+        let inner = Arc::new(inner);
+        Self {
+            tower: layer.layer(McpTowerService::new(inner.clone())),
+            inner,
+        }
+    }
+}
+
+impl<T, S> std::fmt::Debug for TowerLayered<T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TowerLayered").finish_non_exhaustive()
+    }
+}
+
+impl<R, T, S> Service<R> for TowerLayered<T, S>
+where
+    R: ServiceRole,
+    S: Service<R>,
+    T: TowerService<McpRequest<R>, Response = R::Resp> + Clone + Send + Sync + 'static,
+    T::Error: Into<BoxError>,
+    T::Future: Send,
+{
+    async fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> Result<R::Resp, McpError> {
+        call_tower(&self.tower, McpRequest { request, context }).await
+    }
+
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+        context: NotificationContext<R>,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
+        Service::handle_notification(self.inner.as_ref(), notification, context)
+    }
+
+    fn get_info(&self) -> R::Info {
+        self.inner.get_info()
+    }
+}
+
+/// A [`Middleware`] as a tower layer over the services of [`McpRequest`]
+#[derive(Debug)]
+pub struct MiddlewareLayer<M> {
+    middleware: Arc<M>,
+}
+
+impl<M> Clone for MiddlewareLayer<M> {
+    fn clone(&self) -> Self {
+        Self {
+            middleware: self.middleware.clone(),
+        }
+    }
+}
+
+impl<M> MiddlewareLayer<M> {
+    pub fn new(middleware: M) -> Self {
+        Self {
+            middleware: Arc::new(middleware),
+        }
+    }
+}
+
+impl<M, T> Layer<T> for MiddlewareLayer<M> {
+    type Service = MiddlewareTowerService<M, T>;
+
+    fn layer(&self, inner: T) -> Self::Service {
+        MiddlewareTowerService {
+            middleware: self.middleware.clone(),
+            inner,
+        }
+    }
+}
+
+/// A tower service wrapped with a [`Middleware`], see [`MiddlewareLayer`]
+#[derive(Debug)]
+pub struct MiddlewareTowerService<M, T> {
+    middleware: Arc<M>,
+    inner: T,
+}
+
+impl<M, T: Clone> Clone for MiddlewareTowerService<M, T> {
+    fn clone(&self) -> Self {
+        Self {
+            middleware: self.middleware.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// The tower service under a middleware, which only sees the requests
+struct TowerNext<T> {
+    service: T,
+}
+
+impl<R, T> NextService<R> for TowerNext<T>
+where
+    R: ServiceRole,
+    T: TowerService<McpRequest<R>, Response = R::Resp> + Clone + Send + Sync,
+    T::Error: Into<BoxError>,
+    T::Future: Send,
+{
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> BoxFuture<'_, Result<R::Resp, McpError>> {
+        Box::pin(call_tower(&self.service, McpRequest { request, context }))
+    }
+
+    fn handle_notification(
+        &self,
+        _notification: R::PeerNot,
+        _context: NotificationContext<R>,
+    ) -> BoxFuture<'_, Result<(), McpError>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+impl<R, M, T> TowerService<McpRequest<R>> for MiddlewareTowerService<M, T>
+where
+    R: ServiceRole,
+    M: Middleware<R>,
+    T: TowerService<McpRequest<R>, Response = R::Resp> + Clone + Send + Sync + 'static,
+    T::Error: Into<BoxError>,
+    T::Future: Send,
+{
+    type Response = R::Resp;
+    type Error = McpError;
+    type Future = BoxFuture<'static, Result<R::Resp, McpError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // the inner service is polled when the middleware calls it
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: McpRequest<R>) -> Self::Future {
+        let middleware = self.middleware.clone();
+        let next = TowerNext {
+            service: self.inner.clone(),
+        };
+        Box::pin(async move {
+            middleware
+                .handle_request(req.request, req.context, Next { inner: &next })
+                .await
+        })
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::{
+        RoleServer, ServerHandler,
+        model::ErrorCode,
+        service::{
+            ServiceExt,
+            middleware::MapError,
+            test_util::{ping, request_context},
+        },
+    };
+
+    struct PingServer;
+    impl ServerHandler for PingServer {}
+
+    /// Fail every request with a tower error
+    #[derive(Clone)]
+    struct Unavailable;
+    impl TowerService<McpRequest<RoleServer>> for Unavailable {
+        type Response = crate::model::ServerResult;
+        type Error = BoxError;
+        type Future = BoxFuture<'static, Result<Self::Response, BoxError>>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, _req: McpRequest<RoleServer>) -> Self::Future {
+            Box::pin(async { Err("overloaded".into()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tower_layers() {
+        let passthrough = PingServer.with_tower_layer(tower_layer::Identity::new());
+        Service::handle_request(&passthrough, ping(), request_context())
+            .await
+            .unwrap();
+
+        // a middleware in a tower stack, in front of a tower error
+        let layer = MiddlewareLayer::new(MapError::new(|error: McpError| {
+            McpError::new(ErrorCode(-32000), error.message, None)
+        }));
+        let unavailable =
+            PingServer.with_tower_layer(tower_layer::layer_fn(|_| layer.layer(Unavailable)));
+        let error = Service::handle_request(&unavailable, ping(), request_context())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode(-32000));
+        assert_eq!(error.message, "overloaded");
+    }
+
+    /// Answer the pings once `release` is notified
+    struct BlockedServer {
+        release: Arc<tokio::sync::Notify>,
+    }
+    impl ServerHandler for BlockedServer {
+        async fn ping(&self, _context: RequestContext<RoleServer>) -> Result<(), McpError> {
+            self.release.notified().await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stock_tower_layers() {
+        tokio::time::pause();
+        let release = Arc::new(tokio::sync::Notify::new());
+        let server = BlockedServer {
+            release: release.clone(),
+        }
+        .with_tower_layer(tower::timeout::TimeoutLayer::new(
+            std::time::Duration::from_secs(1),
+        ));
+        let error = Service::handle_request(&server, ping(), request_context())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::REQUEST_TIMEOUT);
+
+        // the second request waits for the first one to finish
+        let server = Arc::new(
+            BlockedServer {
+                release: release.clone(),
+            }
+            .with_tower_layer(tower::limit::ConcurrencyLimitLayer::new(1)),
+        );
+        let first = tokio::spawn({
+            let server = server.clone();
+            async move { Service::handle_request(server.as_ref(), ping(), request_context()).await }
+        });
+        tokio::task::yield_now().await;
+        let mut second = std::pin::pin!(Service::handle_request(
+            server.as_ref(),
+            ping(),
+            request_context()
+        ));
+        assert!(futures::poll!(second.as_mut()).is_pending());
+        release.notify_one();
+        first.await.unwrap().unwrap();
+        release.notify_one();
+        second.await.unwrap();
+    }
+}
@@ -6,13 +6,15 @@ use super::*;
 use crate::{
     model::{
         CancelledNotification, CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage,
-        ClientNotification, ClientRequest, ClientResult, CreateMessageRequest,
-        CreateMessageRequestParam, CreateMessageResult, ErrorData, ListRootsRequest,
-        ListRootsResult, LoggingMessageNotification, LoggingMessageNotificationParam,
-        ProgressNotification, ProgressNotificationParam, PromptListChangedNotification,
-        ProtocolVersion, ResourceListChangedNotification, ResourceUpdatedNotification,
-        ResourceUpdatedNotificationParam, ServerInfo, ServerNotification, ServerRequest,
-        ServerResult, ToolListChangedNotification,
+        ClientNotification, ClientRequest, ClientResult, CreateElicitationRequest,
+        CreateElicitationRequestParam, CreateElicitationResult, CreateMessageRequest,
+        CreateMessageRequestParam, CreateMessageResult, ElicitationAction, ErrorData,
+        ListRootsRequest, ListRootsResult, LoggingLevel, LoggingMessageNotification,
+        LoggingMessageNotificationParam, ProgressNotification, ProgressNotificationParam,
+        PromptListChangedNotification, ProtocolVersion, ResourceListChangedNotification,
+        ResourceUpdatedNotification, ResourceUpdatedNotificationParam, ServerInfo,
+        ServerJsonRpcMessage, ServerNotification, ServerRequest, ServerResult,
+        ToolListChangedNotification,
     },
     transport::DynamicTransportError,
 };
@@ -32,6 +34,19 @@ impl ServiceRole for RoleServer {
 
     type InitializeError = ServerInitializeError;
     const IS_CLIENT: bool = false;
+    fn negotiated_protocol_version(
+        info: &Self::Info,
+        peer_info: &Self::PeerInfo,
+    ) -> Option<ProtocolVersion> {
+        // the older of the two versions is used, see `serve_server_with_ct_inner`
+        match peer_info
+            .protocol_version
+            .partial_cmp(&info.protocol_version)?
+        {
+            std::cmp::Ordering::Less => Some(peer_info.protocol_version.clone()),
+            _ => Some(info.protocol_version.clone()),
+        }
+    }
 }
 
 /// It represents the error that may occur when serving the server.
@@ -81,9 +96,10 @@ impl ServerInitializeError {
 pub type ClientSink = Peer<RoleServer>;
 
 impl<S: Service<RoleServer>> ServiceExt<RoleServer> for S {
-    fn serve_with_ct<T, E, A>(
+    fn serve_with_config_and_ct<T, E, A>(
         self,
         transport: T,
+        config: ServiceConfig,
         ct: CancellationToken,
     ) -> impl Future<Output = Result<RunningService<RoleServer, Self>, ServerInitializeError>> + Send
     where
@@ -91,7 +107,7 @@ impl<S: Service<RoleServer>> ServiceExt<RoleServer> for S {
         E: std::error::Error + Send + Sync + 'static,
         Self: Sized,
     {
-        serve_server_with_ct(self, transport, ct)
+        serve_server_with_config_and_ct(self, transport, config, ct)
     }
 }
 
@@ -158,13 +174,27 @@ pub async fn serve_server_with_ct<S, T, E, A>(
     transport: T,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
+where
+    S: Service<RoleServer>,
+    T: IntoTransport<RoleServer, E, A>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    serve_server_with_config_and_ct(service, transport, ServiceConfig::default(), ct).await
+}
+
+pub async fn serve_server_with_config_and_ct<S, T, E, A>(
+    service: S,
+    transport: T,
+    config: ServiceConfig,
+    ct: CancellationToken,
+) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
 where
     S: Service<RoleServer>,
     T: IntoTransport<RoleServer, E, A>,
     E: std::error::Error + Send + Sync + 'static,
 {
     tokio::select! {
-        result = serve_server_with_ct_inner(service, transport.into_transport(), ct.clone()) => { result }
+        result = serve_server_with_ct_inner(service, transport.into_transport(), config, ct.clone()) => { result }
         _ = ct.cancelled() => {
             Err(ServerInitializeError::Cancelled)
         }
@@ -174,6 +204,7 @@ where
 async fn serve_server_with_ct_inner<S, T>(
     service: S,
     transport: T,
+    config: ServiceConfig,
     ct: CancellationToken,
 ) -> Result<RunningService<RoleServer, S>, ServerInitializeError>
 where
@@ -250,7 +281,7 @@ where
     };
     let _ = service.handle_notification(notification, context).await;
     // Continue processing service
-    Ok(serve_inner(service, transport, peer, peer_rx, ct))
+    Ok(serve_inner(service, transport, peer, peer_rx, config, ct))
 }
 
 macro_rules! method {
@@ -326,9 +357,72 @@ macro_rules! method {
     };
 }
 
+/// The typed response of [`Peer::<RoleServer>::elicit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElicitationResponse<T> {
+    /// The user submitted the requested data
+    Accept(T),
+    /// The user explicitly declined to provide the data
+    Decline,
+    /// The user dismissed the request without making a choice
+    Cancel,
+}
+
+impl<T> ElicitationResponse<T> {
+    /// The accepted value, if any
+    pub fn accepted(self) -> Option<T> {
+        match self {
+            ElicitationResponse::Accept(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
 impl Peer<RoleServer> {
     method!(peer_req create_message CreateMessageRequest(CreateMessageRequestParam) => CreateMessageResult);
     method!(peer_req list_roots ListRootsRequest() => ListRootsResult);
+    method!(peer_req create_elicitation CreateElicitationRequest(CreateElicitationRequestParam) => CreateElicitationResult);
+
+    /// Whether the client declared the elicitation capability during initialization
+    pub fn supports_elicitation(&self) -> bool {
+        self.peer_info()
+            .is_some_and(|info| info.capabilities.elicitation.is_some())
+    }
+
+    /// Ask the user for a value of type `T`.
+    ///
+    /// The JSON schema of `T` is sent as the requested schema, and the accepted content is
+    /// deserialized back into `T`. Content which doesn't match `T` is reported as an
+    /// invalid params error.
+    pub async fn elicit<T>(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<ElicitationResponse<T>, ServiceError>
+    where
+        T: schemars::JsonSchema + serde::de::DeserializeOwned,
+    {
+        let result = self
+            .create_elicitation(CreateElicitationRequestParam {
+                message: message.into(),
+                requested_schema: crate::handler::server::tool::schema_for_type::<T>(),
+            })
+            .await?;
+        match result.action {
+            ElicitationAction::Accept => {
+                let content = result.content.unwrap_or_default();
+                let value =
+                    serde_json::from_value(serde_json::Value::Object(content)).map_err(|e| {
+                        ServiceError::McpError(ErrorData::invalid_params(
+                            format!("elicitation content doesn't match the requested schema: {e}"),
+                            None,
+                        ))
+                    })?;
+                Ok(ElicitationResponse::Accept(value))
+            }
+            ElicitationAction::Decline => Ok(ElicitationResponse::Decline),
+            ElicitationAction::Cancel => Ok(ElicitationResponse::Cancel),
+        }
+    }
 
     method!(peer_not notify_cancelled CancelledNotification(CancelledNotificationParam));
     method!(peer_not notify_progress ProgressNotification(ProgressNotificationParam));
@@ -337,4 +431,19 @@ impl Peer<RoleServer> {
     method!(peer_not notify_resource_list_changed ResourceListChangedNotification);
     method!(peer_not notify_tool_list_changed ToolListChangedNotification);
     method!(peer_not notify_prompt_list_changed PromptListChangedNotification);
+
+    /// The minimum level of the log messages the client asked for through `logging/setLevel`
+    pub fn logging_level(&self) -> Option<LoggingLevel> {
+        *self
+            .logging_level
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_logging_level(&self, level: LoggingLevel) {
+        *self
+            .logging_level
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(level);
+    }
 }
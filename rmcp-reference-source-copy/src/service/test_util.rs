@@ -0,0 +1,29 @@
+//! Fixtures shared by the unit tests
+use std::sync::Arc;
+
+use super::{AtomicU32RequestIdProvider, Peer, ProxyOutbound, RequestContext, ServiceRole};
+use crate::model::{ClientRequest, NumberOrString, PingRequest};
+
+/// A peer without a transport, its outbound messages are left in the receiver
+pub(crate) fn peer<R: ServiceRole>() -> (Peer<R>, ProxyOutbound<R>) {
+    Peer::new(Arc::new(AtomicU32RequestIdProvider::default()), None)
+}
+
+/// The context of a request with the id 1, from a peer without a transport
+pub(crate) fn request_context<R: ServiceRole>() -> RequestContext<R> {
+    let (peer, _) = self::peer();
+    RequestContext {
+        ct: Default::default(),
+        id: NumberOrString::Number(1),
+        meta: Default::default(),
+        extensions: Default::default(),
+        peer,
+    }
+}
+
+pub(crate) fn ping() -> ClientRequest {
+    ClientRequest::PingRequest(PingRequest {
+        method: Default::default(),
+        extensions: Default::default(),
+    })
+}
@@ -0,0 +1,127 @@
+use std::{borrow::Cow, collections::HashMap, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::model::Meta;
+
+/// Deadlines for handling the requests from the peer.
+///
+/// When a deadline passes, [`RequestContext::ct`](crate::service::RequestContext::ct) is
+/// cancelled and the peer gets a [`ErrorCode::REQUEST_TIMEOUT`](crate::model::ErrorCode::REQUEST_TIMEOUT)
+/// error. No deadline is applied by default.
+///
+/// A peer can send the time it's willing to wait in the `timeoutMs` field of the request
+/// `_meta`, it's honored unless [`TimeoutConfig::ignore_peer_hint`] is set. The hint can only
+/// shorten the configured timeout, and is capped to [`TimeoutConfig::MAX_PEER_HINT`].
+///
+/// # Example
+/// ```rust
+/// # use std::time::Duration;
+/// # use rmcp::service::TimeoutConfig;
+/// let config = TimeoutConfig::default()
+///     .default_timeout(Duration::from_secs(30))
+///     .method_timeout("tools/call", Duration::from_secs(300));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutConfig {
+    /// The timeout of the methods without their own timeout
+    pub default: Option<Duration>,
+    pub per_method: HashMap<Cow<'static, str>, Duration>,
+    pub ignore_peer_hint: bool,
+}
+
+impl TimeoutConfig {
+    /// The longest timeout a peer can ask for
+    pub const MAX_PEER_HINT: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.default = Some(timeout);
+        self
+    }
+    pub fn method_timeout(
+        mut self,
+        method: impl Into<Cow<'static, str>>,
+        timeout: Duration,
+    ) -> Self {
+        self.per_method.insert(method.into(), timeout);
+        self
+    }
+    pub fn ignore_peer_hint(mut self) -> Self {
+        self.ignore_peer_hint = true;
+        self
+    }
+
+    /// The timeout of a request, the shortest of the configured one and the peer hint
+    pub fn timeout(&self, method: &str, meta: &Meta) -> Option<Duration> {
+        let configured = self.per_method.get(method).copied().or(self.default);
+        let hint = if self.ignore_peer_hint {
+            None
+        } else {
+            meta.get_timeout().map(|hint| hint.min(Self::MAX_PEER_HINT))
+        };
+        match (configured, hint) {
+            (Some(configured), Some(hint)) => Some(configured.min(hint)),
+            (configured, hint) => configured.or(hint),
+        }
+    }
+
+    /// The deadline of a request received now, `None` if it's too far to be represented
+    pub fn deadline(&self, method: &str, meta: &Meta) -> Option<Instant> {
+        self.timeout(method, meta)
+            .and_then(|timeout| Instant::now().checked_add(timeout))
+    }
+}
+
+/// The deadline of a request, in the [`RequestContext::extensions`](crate::service::RequestContext::extensions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestDeadline(pub Instant);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_resolution() {
+        let config = TimeoutConfig::default()
+            .default_timeout(Duration::from_secs(10))
+            .method_timeout("tools/call", Duration::from_secs(60));
+        let mut meta = Meta::new();
+        assert_eq!(config.timeout("ping", &meta), Some(Duration::from_secs(10)));
+        assert_eq!(
+            config.timeout("tools/call", &meta),
+            Some(Duration::from_secs(60))
+        );
+
+        meta.set_timeout(Duration::from_secs(5));
+        assert_eq!(
+            config.timeout("tools/call", &meta),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            TimeoutConfig::default().timeout("ping", &meta),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            config.clone().ignore_peer_hint().timeout("ping", &meta),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(TimeoutConfig::default().timeout("ping", &Meta::new()), None);
+    }
+
+    #[test]
+    fn test_huge_peer_hint() {
+        let mut meta = Meta::new();
+        meta.insert("timeoutMs".to_owned(), u64::MAX.into());
+        let config = TimeoutConfig::default();
+        assert_eq!(
+            config.timeout("ping", &meta),
+            Some(TimeoutConfig::MAX_PEER_HINT)
+        );
+        assert!(config.deadline("ping", &meta).is_some());
+        // a configured timeout too far away isn't a deadline
+        let config = TimeoutConfig::default()
+            .default_timeout(Duration::MAX)
+            .ignore_peer_hint();
+        assert_eq!(config.deadline("ping", &meta), None);
+    }
+}
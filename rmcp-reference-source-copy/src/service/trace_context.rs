@@ -0,0 +1,252 @@
+//! W3C trace context propagation through `_meta`
+//!
+//! The requests sent by a [`Peer`](super::Peer) carry the `traceparent` and `tracestate` of
+//! the current span, and the requests received are handled in a span whose parent is the one
+//! they carry, so that a trace follows the requests across the MCP hops.
+use std::{any::Any, str::FromStr};
+
+use opentelemetry::{
+    Context,
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+};
+use tracing::field::Empty;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use super::ServiceRole;
+use crate::{
+    error::ErrorData as McpError,
+    model::{ClientRequest, GetMeta, GetMethod, Meta, RequestId},
+};
+
+const VERSION: &str = "00";
+const INVALID_VERSION: &str = "ff";
+
+fn is_lower_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn format_traceparent(span_context: &SpanContext) -> String {
+    let flags = u8::from(span_context.is_sampled());
+    format!(
+        "{VERSION}-{:032x}-{:016x}-{flags:02x}",
+        span_context.trace_id(),
+        span_context.span_id()
+    )
+}
+
+/// The remote span context carried by `_meta`, if it's valid
+fn extract(meta: &Meta) -> Option<SpanContext> {
+    let mut fields = meta.get_traceparent()?.trim().split('-');
+    let version = fields.next()?;
+    let trace_id = fields.next()?;
+    let span_id = fields.next()?;
+    let flags = fields.next()?;
+    // the later versions may append fields
+    if !is_lower_hex(version, 2) || version == INVALID_VERSION {
+        return None;
+    }
+    if version == VERSION && fields.next().is_some() {
+        return None;
+    }
+    if !is_lower_hex(trace_id, 32) || !is_lower_hex(span_id, 16) || !is_lower_hex(flags, 2) {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    let trace_state = meta
+        .get_tracestate()
+        .and_then(|tracestate| TraceState::from_str(tracestate).ok())
+        .unwrap_or_default();
+    let span_context = SpanContext::new(
+        TraceId::from_hex(trace_id).ok()?,
+        SpanId::from_hex(span_id).ok()?,
+        TraceFlags::new(flags) & TraceFlags::SAMPLED,
+        true,
+        trace_state,
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+/// Put the context of the current span in `_meta`, unless it already carries one
+pub(crate) fn inject_current_context(meta: &mut Meta) {
+    if meta.get_traceparent().is_some() {
+        return;
+    }
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return;
+    }
+    meta.set_traceparent(format_traceparent(span_context));
+    let tracestate = span_context.trace_state().header();
+    if !tracestate.is_empty() {
+        meta.set_tracestate(tracestate);
+    }
+}
+
+/// The attribute naming what a client request is about, such as the tool it calls
+fn request_target(request: &dyn Any) -> Option<(&'static str, &str)> {
+    match request.downcast_ref::<ClientRequest>()? {
+        ClientRequest::CallToolRequest(request) => {
+            Some(("gen_ai.tool.name", request.params.name.as_ref()))
+        }
+        ClientRequest::GetPromptRequest(request) => {
+            Some(("gen_ai.prompt.name", request.params.name.as_str()))
+        }
+        ClientRequest::ReadResourceRequest(request) => {
+            Some(("mcp.resource.uri", request.params.uri.as_str()))
+        }
+        ClientRequest::SubscribeRequest(request) => {
+            Some(("mcp.resource.uri", request.params.uri.as_str()))
+        }
+        ClientRequest::UnsubscribeRequest(request) => {
+            Some(("mcp.resource.uri", request.params.uri.as_str()))
+        }
+        _ => None,
+    }
+}
+
+/// The span handling a request from the peer, child of the span the peer sent it from
+pub(crate) fn request_span<R: ServiceRole>(request: &R::PeerReq, id: &RequestId) -> tracing::Span {
+    let method = request.method();
+    let target = request_target(request);
+    let name = match target {
+        Some((_, target)) => format!("{method} {target}"),
+        None => method.to_owned(),
+    };
+    let span = tracing::info_span!(
+        "mcp.request",
+        otel.name = %name,
+        otel.kind = "server",
+        otel.status_code = Empty,
+        otel.status_description = Empty,
+        rpc.system = "jsonrpc",
+        mcp.method.name = method,
+        jsonrpc.request.id = %id,
+        rpc.jsonrpc.error_code = Empty,
+        gen_ai.tool.name = Empty,
+        gen_ai.prompt.name = Empty,
+        mcp.resource.uri = Empty,
+    );
+    if let Some((field, value)) = target {
+        span.record(field, value);
+    }
+    if let Some(parent) = extract(request.get_meta()) {
+        span.set_parent(Context::new().with_remote_span_context(parent));
+    }
+    span
+}
+
+/// Mark the current request span as failed
+pub(crate) fn record_error(error: &McpError) {
+    let span = tracing::Span::current();
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_description", &*error.message);
+    span.record("rpc.jsonrpc.error_code", error.code.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(traceparent: &str) -> Meta {
+        let mut meta = Meta::new();
+        meta.set_traceparent(traceparent);
+        meta
+    }
+
+    #[test]
+    fn test_extract_traceparent() {
+        let mut valid = meta("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        valid.set_tracestate("vendor=value");
+        let span_context = extract(&valid).unwrap();
+        assert!(span_context.is_remote());
+        assert!(span_context.is_sampled());
+        assert_eq!(span_context.trace_state().get("vendor"), Some("value"));
+        assert_eq!(
+            format_traceparent(&span_context),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        // a later version may have more fields
+        assert!(
+            extract(&meta(
+                "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-x"
+            ))
+            .is_some()
+        );
+
+        for invalid in [
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-x",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "not a traceparent",
+        ] {
+            assert!(extract(&meta(invalid)).is_none(), "{invalid}");
+        }
+        assert!(extract(&Meta::new()).is_none());
+    }
+
+    #[test]
+    fn test_inject_keeps_explicit_context() {
+        let mut explicit = meta("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        inject_current_context(&mut explicit);
+        assert_eq!(
+            explicit.get_traceparent(),
+            Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+        );
+        // no opentelemetry layer, so no context to inject
+        let mut empty = Meta::new();
+        inject_current_context(&mut empty);
+        assert!(empty.get_traceparent().is_none());
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_request_span_is_exported_under_the_remote_parent() {
+        use opentelemetry::trace::TracerProvider;
+        use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use crate::{RoleServer, model::NumberOrString, service::test_util};
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("rmcp")));
+
+        let mut request = test_util::ping();
+        *request.get_meta_mut() = meta("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        let mut injected = Meta::new();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = request_span::<RoleServer>(&request, &NumberOrString::Number(1));
+            span.in_scope(|| inject_current_context(&mut injected));
+        });
+        provider.force_flush().unwrap();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.name, "ping");
+        assert_eq!(
+            span.span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+        assert_eq!(
+            span.parent_span_id,
+            SpanId::from_hex("00f067aa0ba902b7").unwrap()
+        );
+        // the requests sent while handling it are children of the request span
+        assert_eq!(
+            injected.get_traceparent(),
+            Some(format_traceparent(&span.span_context).as_str())
+        );
+    }
+}
@@ -9,6 +9,7 @@
 //! | std IO            | [`child_process::TokioChildProcess`]                      | [`io::stdio`]                                         |
 //! | streamable http   | [`streamable_http_client::StreamableHttpClientTransport`] | [`streamable_http_server::StreamableHttpService`]     |
 //! | sse               | [`sse_client::SseClientTransport`]                        | [`sse_server::SseServer`]                             |
+//! | websocket         | [`ws::connect`]                                           | [`ws::accept`]                                        |
 //!
 //！## Helper Transport Types
 //! Thers are several helper transport types that can help you to create transport quickly.
@@ -28,6 +29,11 @@
 //!
 //! This could be very helpful when you want to create a transport from a duplex object stream, such as a websocket connection.
 //!
+//! ### [Record/Replay Transport](`record::RecordingTransport`)
+//! You need to enable `transport-record` feature to use this transport.
+//!
+//! This transport records the messages of another transport to a JSONL file, and a [`record::ReplayTransport`] plays a recording back to check that a service still sends the same messages.
+//!
 //! ## [IntoTransport](`IntoTransport`) trait
 //! [`IntoTransport`] is a helper trait that implicitly convert a type into a transport type.
 //!
@@ -74,6 +80,10 @@ pub mod sink_stream;
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-async-rw")))]
 pub mod async_rw;
 
+#[cfg(feature = "transport-record")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-record")))]
+pub mod record;
+
 #[cfg(feature = "transport-worker")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-worker")))]
 pub mod worker;
@@ -116,9 +126,12 @@ pub mod auth;
 #[cfg_attr(docsrs, doc(cfg(feature = "auth")))]
 pub use auth::{AuthError, AuthorizationManager, AuthorizationSession, AuthorizedHttpClient};
 
-// #[cfg(feature = "transport-ws")]
-// #[cfg_attr(docsrs, doc(cfg(feature = "transport-ws")))]
-// pub mod ws;
+#[cfg(feature = "transport-ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-ws")))]
+pub mod ws;
+#[cfg(feature = "transport-ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-ws")))]
+pub use ws::WsTransport;
 #[cfg(feature = "transport-streamable-http-server-session")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-server-session")))]
 pub mod streamable_http_server;
@@ -181,6 +194,23 @@ where
     }
 }
 
+/// Whether a message is received from the peer or sent to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Received => "received",
+            Direction::Sent => "sent",
+        }
+    }
+}
+
 /// A transport that can send a single message and then close itself
 pub struct OneshotTransport<R>
 where
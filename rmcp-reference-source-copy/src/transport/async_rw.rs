@@ -197,20 +197,18 @@ fn try_parse_with_compatibility<T: serde::de::DeserializeOwned>(
                 // Check if this is a non-standard notification that should be ignored
                 if line_str.contains("\"method\":\"notifications/") {
                     // Extract the method name to check if it's standard
-                    if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line_str) {
-                        if let Some(method) = json_value.get("method").and_then(|m| m.as_str()) {
-                            if method.starts_with("notifications/")
-                                && !is_standard_notification(method)
-                            {
-                                tracing::debug!(
-                                    "Ignoring non-standard notification {} {}: {}",
-                                    method,
-                                    context,
-                                    line_str
-                                );
-                                return Ok(None); // Skip this message
-                            }
-                        }
+                    if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line_str)
+                        && let Some(method) = json_value.get("method").and_then(|m| m.as_str())
+                        && method.starts_with("notifications/")
+                        && !is_standard_notification(method)
+                    {
+                        tracing::debug!(
+                            "Ignoring non-standard notification {} {}: {}",
+                            method,
+                            context,
+                            line_str
+                        );
+                        return Ok(None); // Skip this message
                     }
                 }
 
@@ -294,6 +292,7 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
                     let newline_index = offset + self.next_index;
                     self.next_index = 0;
                     let line = buf.split_to(newline_index + 1);
+                    record_bytes(false, line.len());
                     let line = &line[..line.len() - 1];
                     let line = without_carriage_return(line);
 
@@ -331,6 +330,7 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
                     None
                 } else {
                     let line = buf.split_to(buf.len());
+                    record_bytes(false, line.len());
                     let line = without_carriage_return(&line);
 
                     // Use compatibility handling function
@@ -345,12 +345,32 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
     }
 }
 
+/// Count the bytes read or written by the codec
+#[inline]
+fn record_bytes(_sent: bool, _bytes: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        let direction = if _sent {
+            crate::transport::Direction::Sent
+        } else {
+            crate::transport::Direction::Received
+        };
+        crate::metrics::Metrics::global().record_transport_bytes(
+            crate::metrics::Transport::AsyncRw,
+            direction,
+            _bytes,
+        );
+    }
+}
+
 impl<T: Serialize> Encoder<T> for JsonRpcMessageCodec<T> {
     type Error = JsonRpcMessageCodecError;
 
     fn encode(&mut self, item: T, buf: &mut BytesMut) -> Result<(), JsonRpcMessageCodecError> {
+        let start = buf.len();
         serde_json::to_writer(buf.writer(), &item)?;
         buf.put_u8(b'\n');
+        record_bytes(true, buf.len() - start);
         Ok(())
     }
 }
@@ -456,13 +456,13 @@ impl AuthorizationManager {
 
         if let Some(creds) = credentials.as_ref() {
             // check if the token is expire
-            if let Some(expires_at) = *self.expires_at.read().await {
-                if expires_at < Instant::now() {
-                    // token expired, try to refresh , release the lock
-                    drop(credentials);
-                    let new_creds = self.refresh_token().await?;
-                    return Ok(new_creds.access_token().secret().to_string());
-                }
+            if let Some(expires_at) = *self.expires_at.read().await
+                && expires_at < Instant::now()
+            {
+                // token expired, try to refresh , release the lock
+                drop(credentials);
+                let new_creds = self.refresh_token().await?;
+                return Ok(new_creds.access_token().secret().to_string());
             }
 
             Ok(creds.access_token().secret().to_string())
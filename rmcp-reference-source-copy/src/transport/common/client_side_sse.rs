@@ -24,10 +24,10 @@ pub struct FixedInterval {
 
 impl SseRetryPolicy for FixedInterval {
     fn retry(&self, current_times: usize) -> Option<Duration> {
-        if let Some(max_times) = self.max_times {
-            if current_times >= max_times {
-                return None;
-            }
+        if let Some(max_times) = self.max_times
+            && current_times >= max_times
+        {
+            return None;
         }
         Some(self.duration)
     }
@@ -67,10 +67,10 @@ impl Default for ExponentialBackoff {
 
 impl SseRetryPolicy for ExponentialBackoff {
     fn retry(&self, current_times: usize) -> Option<Duration> {
-        if let Some(max_times) = self.max_times {
-            if current_times >= max_times {
-                return None;
-            }
+        if let Some(max_times) = self.max_times
+            && current_times >= max_times
+        {
+            return None;
         }
         Some(self.base_duration * (2u32.pow(current_times as u32)))
     }
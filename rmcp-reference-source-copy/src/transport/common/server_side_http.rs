@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use std::{convert::Infallible, fmt::Display, sync::Arc, time::Duration};
 
-use bytes::{Buf, Bytes};
+use bytes::Bytes;
 use http::Response;
 use http_body::Body;
 use http_body_util::{BodyExt, Empty, Full, combinators::BoxBody};
@@ -69,6 +69,12 @@ pub(crate) fn sse_stream_response(
     use futures::StreamExt;
     let stream = SseBody::new(stream.map(|message| {
         let data = serde_json::to_string(&message.message).expect("valid message");
+        #[cfg(feature = "metrics")]
+        crate::metrics::Metrics::global().record_transport_bytes(
+            crate::metrics::Transport::StreamableHttp,
+            crate::transport::Direction::Sent,
+            data.len(),
+        );
         let mut sse = Sse::default().data(data);
         sse.id = message.event_id;
         Result::<Sse, Infallible>::Ok(sse)
@@ -120,7 +126,14 @@ where
 {
     match body.collect().await {
         Ok(bytes) => {
-            match serde_json::from_reader::<_, ClientJsonRpcMessage>(bytes.aggregate().reader()) {
+            let bytes = bytes.to_bytes();
+            #[cfg(feature = "metrics")]
+            crate::metrics::Metrics::global().record_transport_bytes(
+                crate::metrics::Transport::StreamableHttp,
+                crate::transport::Direction::Received,
+                bytes.len(),
+            );
+            match serde_json::from_slice::<ClientJsonRpcMessage>(&bytes) {
                 Ok(message) => Ok(message),
                 Err(e) => {
                     let response = Response::builder()
@@ -0,0 +1,653 @@
+//! Record and replay the messages of a transport, for golden tests
+//!
+//! A [`RecordingTransport`] wraps a transport and writes every message it sends and receives
+//! to a JSONL file, one [`RecordedMessage`] per line.
+//!
+//! A [`ReplayTransport`] plays the received side of a [`Recording`] to the service under test,
+//! and checks that the service sends the same messages as the recorded one. The messages
+//! are compared after going through the [`Normalizer`]s, to ignore what is expected to change
+//! from one run to another, such as the request ids or the timestamps.
+//!
+//! ```rust,ignore
+//! // record a session with a real client
+//! let transport = record::record(stdio(), "session.jsonl")?;
+//! Counter::new().serve(transport).await?.waiting().await?;
+//!
+//! // and check that the server still behaves the same
+//! let recording = Recording::load("session.jsonl")?;
+//! let (transport, replay) = ReplayTransport::<RoleServer>::new(recording);
+//! let transport = transport.with_normalizer(NormalizeTimestamps);
+//! let _server = Counter::new().serve(transport).await?;
+//! replay.finish().await?;
+//! ```
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    io::Write,
+    marker::PhantomData,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{sync::Notify, time::Instant};
+
+pub use super::Direction;
+use super::{IntoTransport, Transport};
+use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
+
+/// How long a replay waits for the service to send an expected message
+pub const DEFAULT_REPLAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    pub message: Value,
+}
+
+/// The messages of a recorded session, in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub messages: Vec<RecordedMessage>,
+}
+
+impl Recording {
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, ReplayError> {
+        let messages = jsonl
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|error| ReplayError::Parse {
+                    line: index + 1,
+                    error,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { messages })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_jsonl(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid recording at line {line}: {error}")]
+    Parse {
+        line: usize,
+        #[source]
+        error: serde_json::Error,
+    },
+    #[error("recorded message can't be replayed: {0}")]
+    Deserialize(#[source] serde_json::Error),
+    #[error("failed to serialize message: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("unexpected message {actual}, expected one of {}", Value::Array(expected.clone()))]
+    Mismatch { expected: Vec<Value>, actual: Value },
+    #[error("expected messages were not sent: {}", Value::Array(missing.clone()))]
+    Incomplete { missing: Vec<Value> },
+}
+
+/// Rewrite a message before it's compared, both the recorded one and the actual one
+pub trait Normalizer: Send + Sync + 'static {
+    fn normalize(&self, message: &mut Value);
+}
+
+impl<F> Normalizer for F
+where
+    F: Fn(&mut Value) + Send + Sync + 'static,
+{
+    fn normalize(&self, message: &mut Value) {
+        self(message)
+    }
+}
+
+/// Ignore the JSON-RPC ids, including the ones of the batch items
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeIds;
+
+impl Normalizer for NormalizeIds {
+    fn normalize(&self, message: &mut Value) {
+        let messages = match message {
+            Value::Array(items) => items.iter_mut().collect(),
+            message => vec![message],
+        };
+        for message in messages {
+            if let Some(id) = message.get_mut("id") {
+                *id = Value::String("<id>".to_owned());
+            }
+        }
+    }
+}
+
+/// Ignore the RFC 3339 timestamps anywhere in the messages
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeTimestamps;
+
+impl Normalizer for NormalizeTimestamps {
+    fn normalize(&self, message: &mut Value) {
+        match message {
+            Value::String(value) if DateTime::parse_from_rfc3339(value).is_ok() => {
+                *value = "<timestamp>".to_owned();
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.normalize(item)),
+            Value::Object(object) => object.values_mut().for_each(|item| self.normalize(item)),
+            _ => {}
+        }
+    }
+}
+
+/// Write the recorded lines from a thread of its own, so that the transport never waits for the
+/// writer
+struct Recorder {
+    lines: Option<std::sync::mpsc::Sender<Vec<u8>>>,
+    written: Option<tokio::sync::oneshot::Receiver<()>>,
+}
+
+impl Recorder {
+    fn new(mut writer: impl Write + Send + 'static) -> Self {
+        let (lines, lines_rx) = std::sync::mpsc::channel::<Vec<u8>>();
+        let (written_tx, written) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            while let Ok(line) = lines_rx.recv() {
+                // flush once the lines recorded meanwhile are written too
+                let result = std::iter::once(line)
+                    .chain(lines_rx.try_iter())
+                    .try_for_each(|line| writer.write_all(&line))
+                    .and_then(|()| writer.flush());
+                if let Err(error) = result {
+                    tracing::warn!(%error, "failed to write recorded message");
+                }
+            }
+            let _ = written_tx.send(());
+        });
+        Self {
+            lines: Some(lines),
+            written: Some(written),
+        }
+    }
+
+    /// Stop recording, resolves once the recorded lines are written
+    fn finish(&mut self) -> impl Future<Output = ()> + Send + 'static {
+        self.lines = None;
+        let written = self.written.take();
+        async move {
+            if let Some(written) = written {
+                let _ = written.await;
+            }
+        }
+    }
+
+    fn record(&self, direction: Direction, message: &impl Serialize) {
+        let Some(lines) = &self.lines else {
+            return;
+        };
+        let line = serde_json::to_value(message).and_then(|message| {
+            serde_json::to_vec(&RecordedMessage {
+                timestamp: Utc::now(),
+                direction,
+                message,
+            })
+        });
+        let mut line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                tracing::warn!(%error, "failed to serialize recorded message");
+                return;
+            }
+        };
+        line.push(b'\n');
+        // the writer only stops once the recorder is dropped
+        let _ = lines.send(line);
+    }
+}
+
+/// A transport writing the messages it sends and receives to a recording
+pub struct RecordingTransport<T> {
+    inner: T,
+    recorder: Recorder,
+}
+
+impl<T> RecordingTransport<T> {
+    pub fn new(inner: T, writer: impl Write + Send + 'static) -> Self {
+        Self {
+            inner,
+            recorder: Recorder::new(writer),
+        }
+    }
+
+    /// Record to a file, which is truncated if it exists
+    pub fn create(inner: T, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Ok(Self::new(inner, std::io::BufWriter::new(file)))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Record anything that can be converted into a transport to a file
+pub fn record<R, E, A>(
+    transport: impl IntoTransport<R, E, A>,
+    path: impl AsRef<Path>,
+) -> std::io::Result<RecordingTransport<impl Transport<R, Error = E>>>
+where
+    R: ServiceRole,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    RecordingTransport::create(transport.into_transport(), path)
+}
+
+impl<R, T> Transport<R> for RecordingTransport<T>
+where
+    R: ServiceRole,
+    T: Transport<R>,
+{
+    type Error = T::Error;
+
+    fn name() -> Cow<'static, str> {
+        format!("Recording<{}>", T::name()).into()
+    }
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        self.recorder.record(Direction::Sent, &item);
+        self.inner.send(item)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<R>> {
+        let message = self.inner.receive().await;
+        if let Some(message) = &message {
+            self.recorder.record(Direction::Received, message);
+        }
+        message
+    }
+
+    /// Also waits for the recording to be written
+    fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        let written = self.recorder.finish();
+        let close = self.inner.close();
+        async move {
+            let result = close.await;
+            written.await;
+            result
+        }
+    }
+}
+
+#[derive(Default)]
+struct Outcome {
+    result: Mutex<Option<Result<(), ReplayError>>>,
+    notify: Notify,
+}
+
+/// The result of a replay, see [`ReplayTransport::new`]
+pub struct ReplayHandle {
+    outcome: Arc<Outcome>,
+}
+
+impl std::fmt::Debug for ReplayHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayHandle").finish_non_exhaustive()
+    }
+}
+
+impl ReplayHandle {
+    /// Wait for the end of the replay, that is the end of the recording, the first message
+    /// that doesn't match, or the service closing the transport
+    pub async fn finish(self) -> Result<(), ReplayError> {
+        loop {
+            let notified = self.outcome.notify.notified();
+            let result = self
+                .outcome
+                .result
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take();
+            if let Some(result) = result {
+                return result;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A transport playing the received side of a recording
+///
+/// A recorded message is received once the service has sent all the messages recorded
+/// before it. The messages sent between two received ones may be sent in any order, since
+/// the requests can be handled concurrently.
+pub struct ReplayTransport<R> {
+    messages: VecDeque<RecordedMessage>,
+    /// The recorded messages the service should send before the next received one
+    expected: Vec<Value>,
+    normalizers: Vec<Box<dyn Normalizer>>,
+    /// The ids of the requests sent by the service, by the recorded ones
+    ids: HashMap<String, Value>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    finished: bool,
+    outcome: Arc<Outcome>,
+    role: PhantomData<fn() -> R>,
+}
+
+impl<R: ServiceRole> ReplayTransport<R> {
+    pub fn new(recording: Recording) -> (Self, ReplayHandle) {
+        let outcome = Arc::new(Outcome::default());
+        (
+            Self {
+                messages: recording.messages.into(),
+                expected: Vec::new(),
+                normalizers: Vec::new(),
+                ids: HashMap::new(),
+                timeout: DEFAULT_REPLAY_TIMEOUT,
+                deadline: None,
+                finished: false,
+                outcome: outcome.clone(),
+                role: PhantomData,
+            },
+            ReplayHandle { outcome },
+        )
+    }
+
+    pub fn with_normalizer(mut self, normalizer: impl Normalizer) -> Self {
+        self.normalizers.push(Box::new(normalizer));
+        self
+    }
+
+    /// How long to wait for the service to send an expected message
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn normalize(&self, message: &Value) -> Value {
+        let mut message = message.clone();
+        for normalizer in &self.normalizers {
+            normalizer.normalize(&mut message);
+        }
+        message
+    }
+
+    fn finish(&mut self, result: Result<(), ReplayError>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        *self
+            .outcome
+            .result
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(result);
+        self.outcome.notify.notify_waiters();
+    }
+
+    fn check(&mut self, item: &TxJsonRpcMessage<R>) -> Result<(), ReplayError> {
+        let actual = serde_json::to_value(item).map_err(ReplayError::Serialize)?;
+        let normalized = self.normalize(&actual);
+        let position = self
+            .expected
+            .iter()
+            .position(|expected| self.normalize(expected) == normalized);
+        let Some(position) = position else {
+            let mismatch = |expected: &[Value], actual: &Value| ReplayError::Mismatch {
+                expected: expected.to_vec(),
+                actual: actual.clone(),
+            };
+            self.finish(Err(mismatch(&self.expected, &actual)));
+            return Err(mismatch(&self.expected, &actual));
+        };
+        let expected = self.expected.remove(position);
+        if let (Some(recorded), Some(id)) = (request_id(&expected), request_id(&actual)) {
+            self.ids.insert(recorded.to_string(), id.clone());
+        }
+        self.deadline = None;
+        Ok(())
+    }
+
+    /// Expect the recorded messages sent before the next received one
+    fn expect_sent(&mut self) {
+        while let Some(RecordedMessage {
+            direction: Direction::Sent,
+            ..
+        }) = self.messages.front()
+        {
+            let recorded = self.messages.pop_front().expect("front is checked");
+            self.expected.push(recorded.message);
+        }
+    }
+
+    /// The responses to the requests of the service carry the ids it chose
+    fn remap_response_id(&self, message: &mut Value) {
+        if message.get("method").is_some() {
+            return;
+        }
+        if let Some(id) = message.get_mut("id")
+            && let Some(actual) = self.ids.get(&id.to_string())
+        {
+            *id = actual.clone();
+        }
+    }
+}
+
+fn request_id(message: &Value) -> Option<&Value> {
+    message.get("method").and(message.get("id"))
+}
+
+impl<R: ServiceRole> Transport<R> for ReplayTransport<R> {
+    type Error = ReplayError;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        let result = if self.finished {
+            Err(ReplayError::Mismatch {
+                expected: Vec::new(),
+                actual: serde_json::to_value(&item).unwrap_or_default(),
+            })
+        } else {
+            self.check(&item)
+        };
+        std::future::ready(result)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<R>> {
+        if self.finished {
+            return None;
+        }
+        self.expect_sent();
+        if !self.expected.is_empty() {
+            // cancelled when the service sends a message, the deadline is kept until it
+            // sends an expected one
+            let deadline = *self
+                .deadline
+                .get_or_insert_with(|| Instant::now() + self.timeout);
+            tokio::time::sleep_until(deadline).await;
+            let missing = std::mem::take(&mut self.expected);
+            self.finish(Err(ReplayError::Incomplete { missing }));
+            return None;
+        }
+        let Some(recorded) = self.messages.pop_front() else {
+            self.finish(Ok(()));
+            return None;
+        };
+        // the service may answer before it receives again
+        self.expect_sent();
+        let mut message = recorded.message;
+        self.remap_response_id(&mut message);
+        match serde_json::from_value(message) {
+            Ok(message) => Some(message),
+            Err(error) => {
+                self.finish(Err(ReplayError::Deserialize(error)));
+                None
+            }
+        }
+    }
+
+    fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        std::future::ready(Ok(()))
+    }
+}
+
+impl<R> Drop for ReplayTransport<R> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let missing = self
+            .expected
+            .drain(..)
+            .chain(
+                self.messages
+                    .drain(..)
+                    .filter(|recorded| recorded.direction == Direction::Sent)
+                    .map(|recorded| recorded.message),
+            )
+            .collect();
+        *self
+            .outcome
+            .result
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some(Err(ReplayError::Incomplete { missing }));
+        self.outcome.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_normalizers() {
+        let mut batch = json!([
+            {"jsonrpc": "2.0", "id": 3, "method": "ping"},
+            {"jsonrpc": "2.0", "id": "a", "result": {"at": "2025-06-18T10:00:00Z", "n": "10"}},
+        ]);
+        NormalizeIds.normalize(&mut batch);
+        NormalizeTimestamps.normalize(&mut batch);
+        assert_eq!(
+            batch,
+            json!([
+                {"jsonrpc": "2.0", "id": "<id>", "method": "ping"},
+                {"jsonrpc": "2.0", "id": "<id>", "result": {"at": "<timestamp>", "n": "10"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_recording() {
+        let recording = Recording::from_jsonl(concat!(
+            r#"{"timestamp":"2025-06-18T10:00:00Z","direction":"received","message":{"jsonrpc":"2.0","id":1,"method":"ping"}}"#,
+            "\n\n",
+            r#"{"timestamp":"2025-06-18T10:00:01Z","direction":"sent","message":{"jsonrpc":"2.0","id":1,"result":{}}}"#,
+            "\n",
+        ))
+        .unwrap();
+        assert_eq!(recording.messages.len(), 2);
+        assert_eq!(recording.messages[1].direction, Direction::Sent);
+
+        let error = Recording::from_jsonl("{}\n").unwrap_err();
+        assert!(matches!(error, ReplayError::Parse { line: 1, .. }));
+    }
+
+    #[cfg(all(feature = "client", feature = "server"))]
+    mod replay {
+        use futures::channel::mpsc;
+
+        use super::*;
+        use crate::{
+            RoleServer, ServerHandler, ServiceExt,
+            model::{ServerCapabilities, ServerInfo},
+            transport::sink_stream::SinkStreamTransport,
+        };
+
+        struct Server {
+            instructions: Option<String>,
+        }
+        impl ServerHandler for Server {
+            fn get_info(&self) -> ServerInfo {
+                ServerInfo {
+                    instructions: self.instructions.clone(),
+                    capabilities: ServerCapabilities::builder().enable_tools().build(),
+                    ..Default::default()
+                }
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+        impl Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        async fn record_session() -> Recording {
+            let buffer = SharedBuffer::default();
+            let (client_tx, server_rx) = mpsc::channel(16);
+            let (server_tx, client_rx) = mpsc::channel(16);
+            let transport = RecordingTransport::new(
+                SinkStreamTransport::new(server_tx, server_rx),
+                buffer.clone(),
+            );
+            let server = tokio::spawn(async move {
+                let server = Server { instructions: None }
+                    .serve(transport)
+                    .await
+                    .unwrap();
+                server.waiting().await.unwrap();
+            });
+            let client = ().serve((client_tx, client_rx)).await.unwrap();
+            client.peer().list_tools(Default::default()).await.unwrap();
+            client.cancel().await.unwrap();
+            server.await.unwrap();
+            let jsonl = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+            Recording::from_jsonl(&jsonl).unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_record_and_replay() {
+            let recording = record_session().await;
+            assert_eq!(recording.messages[0].direction, Direction::Received);
+            assert_eq!(recording.messages[0].message["method"], "initialize");
+
+            let (transport, replay) = ReplayTransport::<RoleServer>::new(recording.clone());
+            let transport = transport
+                .with_normalizer(NormalizeIds)
+                .with_normalizer(NormalizeTimestamps);
+            let _server = Server { instructions: None }
+                .serve(transport)
+                .await
+                .unwrap();
+            replay.finish().await.unwrap();
+
+            // the changed server answers the initialize request differently
+            let (transport, replay) = ReplayTransport::<RoleServer>::new(recording);
+            let changed = Server {
+                instructions: Some("changed".to_owned()),
+            };
+            let _ = changed.serve(transport).await;
+            let error = replay.finish().await.unwrap_err();
+            assert!(matches!(error, ReplayError::Mismatch { .. }), "{error}");
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::{collections::HashMap, io, net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
     Extension, Json, Router,
+    body::Bytes,
     extract::{NestedPath, Query, State},
     http::{StatusCode, request::Parts},
     response::{
@@ -65,8 +66,16 @@ async fn post_event_handler(
     State(app): State<App>,
     Query(PostEventQuery { session_id }): Query<PostEventQuery>,
     parts: Parts,
-    Json(mut message): Json<ClientJsonRpcMessage>,
+    body: Bytes,
 ) -> Result<StatusCode, StatusCode> {
+    #[cfg(feature = "metrics")]
+    crate::metrics::Metrics::global().record_transport_bytes(
+        crate::metrics::Transport::Sse,
+        crate::transport::Direction::Received,
+        body.len(),
+    );
+    let Json(mut message) =
+        Json::<ClientJsonRpcMessage>::from_bytes(&body).map_err(|rejection| rejection.status())?;
     tracing::debug!(session_id, ?parts, ?message, "new client message");
     let tx = {
         let rg = app.txs.read().await;
@@ -126,7 +135,15 @@ async fn sse_handler(
     ))
     .chain(ReceiverStream::new(to_client_rx).map(|message| {
         match serde_json::to_string(&message) {
-            Ok(bytes) => Ok(Event::default().event("message").data(&bytes)),
+            Ok(bytes) => {
+                #[cfg(feature = "metrics")]
+                crate::metrics::Metrics::global().record_transport_bytes(
+                    crate::metrics::Transport::Sse,
+                    crate::transport::Direction::Sent,
+                    bytes.len(),
+                );
+                Ok(Event::default().event("message").data(&bytes))
+            }
             Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
         }
     }));
@@ -24,6 +24,14 @@ pub trait SessionManager: Send + Sync + 'static {
     ) -> impl Future<Output = Result<ServerJsonRpcMessage, Self::Error>> + Send;
     fn has_session(&self, id: &SessionId)
     -> impl Future<Output = Result<bool, Self::Error>> + Send;
+    /// Whether a stream of the session can be resumed, this may be true even if the session
+    /// itself is gone, as long as its events are still stored.
+    fn has_resumable_session(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send {
+        self.has_session(id)
+    }
     fn close_session(&self, id: &SessionId)
     -> impl Future<Output = Result<(), Self::Error>> + Send;
     fn create_stream(
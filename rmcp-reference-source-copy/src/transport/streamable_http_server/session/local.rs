@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, HashSet},
     num::ParseIntError,
     sync::Arc,
     time::Duration,
@@ -14,6 +14,9 @@ use tokio::sync::{
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::instrument;
 
+pub mod event_store;
+use event_store::{EventStore, InMemoryEventStore, StoredEvent, StreamKey};
+
 use crate::{
     RoleServer,
     model::{
@@ -69,12 +72,29 @@ impl SessionManager for LocalSessionManager {
         if let Some(handle) = sessions.remove(id) {
             handle.close().await?;
         }
+        self.session_config
+            .event_store
+            .remove_session(id.clone())
+            .await
+            .map_err(SessionError::EventStore)?;
         Ok(())
     }
     async fn has_session(&self, id: &SessionId) -> Result<bool, Self::Error> {
         let sessions = self.sessions.read().await;
         Ok(sessions.contains_key(id))
     }
+    async fn has_resumable_session(&self, id: &SessionId) -> Result<bool, Self::Error> {
+        if self.has_session(id).await? {
+            return Ok(true);
+        }
+        let has_events = self
+            .session_config
+            .event_store
+            .has_session(id.clone())
+            .await
+            .map_err(SessionError::EventStore)?;
+        Ok(has_events)
+    }
     async fn create_stream(
         &self,
         id: &SessionId,
@@ -108,12 +128,31 @@ impl SessionManager for LocalSessionManager {
         id: &SessionId,
         last_event_id: String,
     ) -> Result<impl Stream<Item = ServerSseMessage> + Send + 'static, Self::Error> {
+        let last_event_id: EventId = last_event_id.parse()?;
         let sessions = self.sessions.read().await;
-        let handle = sessions
-            .get(id)
-            .ok_or(LocalSessionManagerError::SessionNotFound(id.clone()))?;
-        let receiver = handle.resume(last_event_id.parse()?).await?;
-        Ok(ReceiverStream::new(receiver.inner))
+        if let Some(handle) = sessions.get(id) {
+            let receiver = handle.resume(last_event_id).await?;
+            return Ok(ReceiverStream::new(receiver.inner));
+        }
+        drop(sessions);
+        // the session is gone (e.g. the process restarted), replay what's left in the event store
+        let stream = StreamKey::new(id.clone(), last_event_id.http_request_id);
+        let events = self
+            .session_config
+            .event_store
+            .replay(stream, last_event_id.index)
+            .await
+            .map_err(SessionError::EventStore)?;
+        if events.is_empty() {
+            return Err(LocalSessionManagerError::SessionNotFound(id.clone()));
+        }
+        let (tx, rx) = tokio::sync::mpsc::channel(events.len());
+        for event in events {
+            let _ = tx
+                .send(stored_event_to_sse(event, last_event_id.http_request_id))
+                .await;
+        }
+        Ok(ReceiverStream::new(rx))
     }
 
     async fn accept_message(
@@ -181,78 +220,85 @@ impl std::str::FromStr for EventId {
 
 use super::{ServerSseMessage, SessionManager};
 
+fn stored_event_to_sse(
+    event: StoredEvent,
+    http_request_id: Option<HttpRequestId>,
+) -> ServerSseMessage {
+    let event_id = EventId {
+        http_request_id,
+        index: event.index,
+    };
+    ServerSseMessage {
+        event_id: Some(event_id.to_string()),
+        message: event.message,
+    }
+}
+
+/// A sender which writes every message through the event store, so it can be replayed.
 struct CachedTx {
     tx: Sender<ServerSseMessage>,
-    cache: VecDeque<ServerSseMessage>,
-    http_request_id: Option<HttpRequestId>,
-    capacity: usize,
+    stream: StreamKey,
+    next_index: usize,
+    store: Arc<dyn EventStore>,
 }
 
 impl CachedTx {
-    fn new(tx: Sender<ServerSseMessage>, http_request_id: Option<HttpRequestId>) -> Self {
+    fn new(tx: Sender<ServerSseMessage>, stream: StreamKey, store: Arc<dyn EventStore>) -> Self {
         Self {
-            cache: VecDeque::with_capacity(tx.capacity()),
-            capacity: tx.capacity(),
             tx,
-            http_request_id,
+            stream,
+            next_index: 0,
+            store,
         }
     }
-    fn new_common(tx: Sender<ServerSseMessage>) -> Self {
-        Self::new(tx, None)
-    }
 
     async fn send(&mut self, message: ServerJsonRpcMessage) {
-        let index = self.cache.back().map_or(0, |m| {
-            m.event_id
-                .as_deref()
-                .unwrap_or_default()
-                .parse::<EventId>()
-                .expect("valid event id")
-                .index
-                + 1
-        });
-        let event_id = EventId {
-            http_request_id: self.http_request_id,
-            index,
-        };
-        let message = ServerSseMessage {
-            event_id: Some(event_id.to_string()),
+        let event = StoredEvent {
+            index: self.next_index,
             message: Arc::new(message),
         };
-        if self.cache.len() >= self.capacity {
-            self.cache.pop_front();
-            self.cache.push_back(message.clone());
-        } else {
-            self.cache.push_back(message.clone());
+        self.next_index += 1;
+        if let Err(e) = self.store.append(self.stream.clone(), event.clone()).await {
+            tracing::error!(index = event.index, "failed to store event: {e}");
         }
+        let message = stored_event_to_sse(event, self.stream.http_request_id);
         let _ = self.tx.send(message).await.inspect_err(|e| {
             let event_id = &e.0.event_id;
             tracing::trace!(?event_id, "trying to send message in a closed session")
         });
     }
 
-    async fn sync(&mut self, index: usize) -> Result<(), SessionError> {
-        let Some(front) = self.cache.front() else {
-            return Ok(());
-        };
-        let front_event_id = front
-            .event_id
-            .as_deref()
-            .unwrap_or_default()
-            .parse::<EventId>()?;
-        let sync_index = index.saturating_sub(front_event_id.index);
-        if sync_index > self.cache.len() {
+    /// Get the stored messages from `index`
+    async fn replay(&self, index: usize) -> Result<Vec<ServerSseMessage>, SessionError> {
+        if index > self.next_index {
             // invalid index
             return Err(SessionError::InvalidEventId);
         }
-        for message in self.cache.iter().skip(sync_index) {
-            let send_result = self.tx.send(message.clone()).await;
-            if send_result.is_err() {
-                let event_id: EventId = message.event_id.as_deref().unwrap_or_default().parse()?;
-                return Err(SessionError::ChannelClosed(Some(event_id.index as u64)));
-            }
+        let events = self
+            .store
+            .replay(self.stream.clone(), index)
+            .await
+            .map_err(SessionError::EventStore)?;
+        Ok(events
+            .into_iter()
+            .map(|event| stored_event_to_sse(event, self.stream.http_request_id))
+            .collect())
+    }
+
+    /// Replace the channel with a new one, the replayed messages are sent first
+    async fn reconnect(
+        &mut self,
+        index: usize,
+        channel_capacity: usize,
+    ) -> Result<Receiver<ServerSseMessage>, SessionError> {
+        let replayed = self.replay(index).await?;
+        // make room for all replayed messages, so this never waits for the receiver
+        let (tx, rx) = tokio::sync::mpsc::channel(channel_capacity + replayed.len());
+        for message in replayed {
+            let _ = tx.send(message).await;
         }
-        Ok(())
+        self.tx = tx;
+        Ok(rx)
     }
 }
 
@@ -276,6 +322,9 @@ pub struct LocalSessionWorker {
     common: CachedTx,
     event_rx: Receiver<SessionEvent>,
     session_config: SessionConfig,
+    /// Counts the session as active until the worker is dropped, however it ends
+    #[cfg(feature = "metrics")]
+    _active: crate::metrics::ActiveSession,
 }
 
 impl LocalSessionWorker {
@@ -302,13 +351,15 @@ pub enum SessionError {
     Io(#[from] std::io::Error),
     #[error("Tokio join error {0}")]
     TokioJoinError(#[from] tokio::task::JoinError),
+    #[error("Event store error: {0}")]
+    EventStore(event_store::EventStoreError),
 }
 
 impl From<SessionError> for std::io::Error {
     fn from(value: SessionError) -> Self {
         match value {
             SessionError::Io(io) => io,
-            _ => std::io::Error::new(std::io::ErrorKind::Other, format!("Session error: {value}")),
+            _ => std::io::Error::other(format!("Session error: {value}")),
         }
     }
 }
@@ -392,7 +443,11 @@ impl LocalSessionWorker {
             http_request_id,
             HttpRequestWise {
                 resources: Default::default(),
-                tx: CachedTx::new(tx, Some(http_request_id)),
+                tx: CachedTx::new(
+                    tx,
+                    StreamKey::new(self.id.clone(), Some(http_request_id)),
+                    self.session_config.event_store.clone(),
+                ),
             },
         );
         tracing::debug!(http_request_id, "establish new request wise channel");
@@ -472,9 +527,23 @@ impl LocalSessionWorker {
                     OutboundChannel::Common
                 }
             }
-            ServerJsonRpcMessage::BatchRequest(_) | ServerJsonRpcMessage::BatchResponse(_) => {
-                // the server side should never yield a batch request or response now
-                unreachable!("server side won't yield batch request or response")
+            ServerJsonRpcMessage::BatchResponse(batch) => {
+                // a batch is answered on the channel of the http request that carried it
+                if let Some(id) = batch.first().and_then(|item| {
+                    self.resource_router
+                        .get(&ResourceKey::McpRequestId(item.id().clone()))
+                }) {
+                    OutboundChannel::RequestWise {
+                        id: *id,
+                        close: false,
+                    }
+                } else {
+                    OutboundChannel::Common
+                }
+            }
+            ServerJsonRpcMessage::BatchRequest(_) => {
+                // the server side should never yield a batch request now
+                unreachable!("server side won't yield batch request")
             }
         }
     }
@@ -502,30 +571,43 @@ impl LocalSessionWorker {
         &mut self,
         last_event_id: EventId,
     ) -> Result<StreamableHttpMessageReceiver, SessionError> {
+        let channel_capacity = self.session_config.channel_capacity;
+        let index = last_event_id.index;
         match last_event_id.http_request_id {
             Some(http_request_id) => {
-                let request_wise = self
-                    .tx_router
-                    .get_mut(&http_request_id)
-                    .ok_or(SessionError::ChannelClosed(Some(http_request_id)))?;
-                let channel = tokio::sync::mpsc::channel(self.session_config.channel_capacity);
-                let (tx, rx) = channel;
-                request_wise.tx.tx = tx;
-                let index = last_event_id.index;
+                let Some(request_wise) = self.tx_router.get_mut(&http_request_id) else {
+                    // the request is completed, replay its stored messages if there are any
+                    let stream = StreamKey::new(self.id.clone(), Some(http_request_id));
+                    let events = self
+                        .session_config
+                        .event_store
+                        .replay(stream, index)
+                        .await
+                        .map_err(SessionError::EventStore)?;
+                    if events.is_empty() {
+                        return Err(SessionError::ChannelClosed(Some(http_request_id)));
+                    }
+                    let (tx, rx) = tokio::sync::mpsc::channel(events.len());
+                    for event in events {
+                        let _ = tx
+                            .send(stored_event_to_sse(event, Some(http_request_id)))
+                            .await;
+                    }
+                    return Ok(StreamableHttpMessageReceiver {
+                        http_request_id: Some(http_request_id),
+                        inner: rx,
+                    });
+                };
                 // sync messages after index
-                request_wise.tx.sync(index).await?;
+                let rx = request_wise.tx.reconnect(index, channel_capacity).await?;
                 Ok(StreamableHttpMessageReceiver {
                     http_request_id: Some(http_request_id),
                     inner: rx,
                 })
             }
             None => {
-                let channel = tokio::sync::mpsc::channel(self.session_config.channel_capacity);
-                let (tx, rx) = channel;
-                self.common.tx = tx;
-                let index = last_event_id.index;
                 // sync messages after index
-                self.common.sync(index).await?;
+                let rx = self.common.reconnect(index, channel_capacity).await?;
                 Ok(StreamableHttpMessageReceiver {
                     http_request_id: None,
                     inner: rx,
@@ -695,23 +777,8 @@ impl LocalSessionHandle {
 
 pub type SessionTransport = WorkerTransport<LocalSessionWorker>;
 
-impl Worker for LocalSessionWorker {
-    type Error = SessionError;
-    type Role = RoleServer;
-    fn err_closed() -> Self::Error {
-        SessionError::TransportClosed
-    }
-    fn err_join(e: tokio::task::JoinError) -> Self::Error {
-        SessionError::TokioJoinError(e)
-    }
-    fn config(&self) -> crate::transport::worker::WorkerConfig {
-        crate::transport::worker::WorkerConfig {
-            name: Some(format!("streamable-http-session-{}", self.id)),
-            channel_buffer_capacity: self.session_config.channel_capacity,
-        }
-    }
-    #[instrument(name = "streamable_http_session", skip_all, fields(id = self.id.as_ref()))]
-    async fn run(mut self, mut context: WorkerContext<Self>) -> Result<(), WorkerQuitReason> {
+impl LocalSessionWorker {
+    async fn serve(&mut self, context: &mut WorkerContext<Self>) -> Result<(), WorkerQuitReason> {
         enum InnerEvent {
             FromHttpService(SessionEvent),
             FromHandler(WorkerSendRequest<LocalSessionWorker>),
@@ -768,14 +835,18 @@ impl Worker for LocalSessionWorker {
                     let to_unregister = match &message {
                         crate::model::JsonRpcMessage::Response(json_rpc_response) => {
                             let request_id = json_rpc_response.id.clone();
-                            Some(ResourceKey::McpRequestId(request_id))
+                            vec![ResourceKey::McpRequestId(request_id)]
                         }
                         crate::model::JsonRpcMessage::Error(json_rpc_error) => {
                             let request_id = json_rpc_error.id.clone();
-                            Some(ResourceKey::McpRequestId(request_id))
+                            vec![ResourceKey::McpRequestId(request_id)]
                         }
+                        crate::model::JsonRpcMessage::BatchResponse(batch) => batch
+                            .iter()
+                            .map(|item| ResourceKey::McpRequestId(item.id().clone()))
+                            .collect(),
                         _ => {
-                            None
+                            vec![]
                             // no need to unregister resource
                         }
                     };
@@ -783,7 +854,7 @@ impl Worker for LocalSessionWorker {
                     let _ = responder.send(handle_result).inspect_err(|error| {
                         tracing::warn!(?error, "failed to send message to http service handler");
                     });
-                    if let Some(to_unregister) = to_unregister {
+                    for to_unregister in to_unregister {
                         self.unregister_resource(&to_unregister);
                     }
                 }
@@ -849,16 +920,56 @@ impl Worker for LocalSessionWorker {
     }
 }
 
+impl Worker for LocalSessionWorker {
+    type Error = SessionError;
+    type Role = RoleServer;
+    fn err_closed() -> Self::Error {
+        SessionError::TransportClosed
+    }
+    fn err_join(e: tokio::task::JoinError) -> Self::Error {
+        SessionError::TokioJoinError(e)
+    }
+    fn config(&self) -> crate::transport::worker::WorkerConfig {
+        crate::transport::worker::WorkerConfig {
+            name: Some(format!("streamable-http-session-{}", self.id)),
+            channel_buffer_capacity: self.session_config.channel_capacity,
+        }
+    }
+    #[instrument(name = "streamable_http_session", skip_all, fields(id = self.id.as_ref()))]
+    async fn run(mut self, mut context: WorkerContext<Self>) -> Result<(), WorkerQuitReason> {
+        let result = self.serve(&mut context).await;
+        // the events are kept for the clients resuming the session, until it's closed or
+        // they expire
+        if let Some(retention) = self.session_config.event_retention {
+            let event_store = self.session_config.event_store.clone();
+            let id = self.id.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(retention).await;
+                if let Err(error) = event_store.remove_session(id.clone()).await {
+                    tracing::warn!(session_id = ?id, %error, "failed to remove session events");
+                }
+            });
+        }
+        result
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionConfig {
     /// the capacity of the channel for the session. Default is 16.
     pub channel_capacity: usize,
     /// if set, the session will be closed after this duration of inactivity.
     pub keep_alive: Option<Duration>,
+    /// where the sent messages are stored for resumption. Default is an [`InMemoryEventStore`].
+    pub event_store: Arc<dyn EventStore>,
+    /// how long the events of a session are kept once its worker stopped, `None` keeps them
+    /// until the session is closed. Default is 5 minutes.
+    pub event_retention: Option<Duration>,
 }
 
 impl SessionConfig {
     pub const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+    pub const DEFAULT_EVENT_RETENTION: Duration = Duration::from_secs(5 * 60);
 }
 
 impl Default for SessionConfig {
@@ -866,6 +977,8 @@ impl Default for SessionConfig {
         Self {
             channel_capacity: Self::DEFAULT_CHANNEL_CAPACITY,
             keep_alive: None,
+            event_store: Arc::new(InMemoryEventStore::default()),
+            event_retention: Some(Self::DEFAULT_EVENT_RETENTION),
         }
     }
 }
@@ -882,7 +995,11 @@ pub fn create_local_session(
     let id = id.into();
     let (event_tx, event_rx) = tokio::sync::mpsc::channel(config.channel_capacity);
     let (common_tx, _) = tokio::sync::mpsc::channel(config.channel_capacity);
-    let common = CachedTx::new_common(common_tx);
+    let common = CachedTx::new(
+        common_tx,
+        StreamKey::new(id.clone(), None),
+        config.event_store.clone(),
+    );
     tracing::info!(session_id = ?id, "create new session");
     let handle = LocalSessionHandle {
         event_tx,
@@ -896,6 +1013,90 @@ pub fn create_local_session(
         common,
         event_rx,
         session_config: config.clone(),
+        #[cfg(feature = "metrics")]
+        _active: crate::metrics::Metrics::global().session_opened(),
     };
     (handle, session_worker)
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::{event_store::FileEventStore, *};
+    use crate::transport::Transport;
+
+    #[tokio::test]
+    async fn test_resume_after_worker_stopped() {
+        let root = std::env::temp_dir().join(format!("rmcp-session-{}", uuid::Uuid::new_v4()));
+        let session_config = SessionConfig {
+            event_store: Arc::new(FileEventStore::new(&root)),
+            ..Default::default()
+        };
+        let manager = LocalSessionManager {
+            sessions: Default::default(),
+            session_config: session_config.clone(),
+        };
+        let (id, mut transport) = manager.create_session().await.unwrap();
+        let handler = tokio::spawn(async move {
+            let (_, request_id) = transport
+                .receive()
+                .await
+                .and_then(|message| message.into_request())
+                .expect("expect the initialize request");
+            let response = serde_json::from_value(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "result": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "serverInfo": { "name": "test", "version": "1.0.0" }
+                }
+            }))
+            .unwrap();
+            transport.send(response).await.unwrap();
+            transport
+        });
+        let initialize = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0.0" }
+            }
+        }))
+        .unwrap();
+        manager.initialize_session(&id, initialize).await.unwrap();
+        let mut transport = handler.await.unwrap();
+        for _ in 0..2 {
+            let notification = serde_json::from_value(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/tools/list_changed"
+            }))
+            .unwrap();
+            transport.send(notification).await.unwrap();
+        }
+        // stop the worker gracefully
+        transport.close().await.unwrap();
+
+        let manager = LocalSessionManager {
+            sessions: Default::default(),
+            session_config,
+        };
+        assert!(manager.has_resumable_session(&id).await.unwrap());
+        let replayed: Vec<_> = manager
+            .resume(&id, "0".to_owned())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(replayed.len(), 2);
+
+        // closing the session removes its events
+        manager.close_session(&id).await.unwrap();
+        assert!(!manager.has_resumable_session(&id).await.unwrap());
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,393 @@
+//! Storage of the events sent through the sse streams of a session.
+//!
+//! Every message sent by [`LocalSessionWorker`](super::LocalSessionWorker) is written to an
+//! [`EventStore`] before it's delivered, and a client reconnecting with a `Last-Event-ID`
+//! header is replayed from the store.
+//!
+//! - [`InMemoryEventStore`] keeps the latest events of each stream in memory, it's the default.
+//! - [`FileEventStore`] appends the events to files, so they can still be replayed after the
+//!   process restarts.
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use futures::future::{BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use super::HttpRequestId;
+use crate::{model::ServerJsonRpcMessage, transport::common::server_side_http::SessionId};
+
+pub type EventStoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Identify a sse stream of a session, `http_request_id` is `None` for the common stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StreamKey {
+    pub session_id: SessionId,
+    pub http_request_id: Option<HttpRequestId>,
+}
+
+impl StreamKey {
+    pub fn new(session_id: SessionId, http_request_id: Option<HttpRequestId>) -> Self {
+        Self {
+            session_id,
+            http_request_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvent {
+    /// The index of the event in its stream
+    pub index: usize,
+    pub message: Arc<ServerJsonRpcMessage>,
+}
+
+pub trait EventStore: std::fmt::Debug + Send + Sync + 'static {
+    /// Append an event to a stream, events of a stream are appended with increasing index.
+    fn append(
+        &self,
+        stream: StreamKey,
+        event: StoredEvent,
+    ) -> BoxFuture<'_, Result<(), EventStoreError>>;
+    /// Get the stored events of a stream with an index greater than or equal to `from`, in order.
+    fn replay(
+        &self,
+        stream: StreamKey,
+        from: usize,
+    ) -> BoxFuture<'_, Result<Vec<StoredEvent>, EventStoreError>>;
+    /// Whether any event of the session is stored
+    fn has_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<bool, EventStoreError>>;
+    /// Remove all events of a session
+    fn remove_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<(), EventStoreError>>;
+}
+
+#[derive(Debug, Default)]
+struct InMemorySession {
+    // ordered by http request id, so the oldest request wise stream is the first `Some` key
+    streams: BTreeMap<Option<HttpRequestId>, VecDeque<StoredEvent>>,
+}
+
+/// Keep the latest events of each stream in memory.
+///
+/// At most `stream_capacity` events are kept for each stream, and at most
+/// `max_request_wise_streams` request wise streams are kept for each session, the oldest
+/// ones are dropped first.
+#[derive(Debug)]
+pub struct InMemoryEventStore {
+    sessions: std::sync::Mutex<HashMap<SessionId, InMemorySession>>,
+    stream_capacity: usize,
+    max_request_wise_streams: usize,
+}
+
+impl InMemoryEventStore {
+    pub const DEFAULT_STREAM_CAPACITY: usize = 16;
+    pub const DEFAULT_MAX_REQUEST_WISE_STREAMS: usize = 64;
+    pub fn new(stream_capacity: usize, max_request_wise_streams: usize) -> Self {
+        Self {
+            sessions: Default::default(),
+            stream_capacity,
+            max_request_wise_streams,
+        }
+    }
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SessionId, InMemorySession>> {
+        self.sessions.lock().expect("event store lock poisoned")
+    }
+}
+
+impl Default for InMemoryEventStore {
+    fn default() -> Self {
+        Self::new(
+            Self::DEFAULT_STREAM_CAPACITY,
+            Self::DEFAULT_MAX_REQUEST_WISE_STREAMS,
+        )
+    }
+}
+
+impl EventStore for InMemoryEventStore {
+    fn append(
+        &self,
+        stream: StreamKey,
+        event: StoredEvent,
+    ) -> BoxFuture<'_, Result<(), EventStoreError>> {
+        let mut sessions = self.lock();
+        let session = sessions.entry(stream.session_id).or_default();
+        let events = session.streams.entry(stream.http_request_id).or_default();
+        if events.len() >= self.stream_capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+        let request_wise_streams = session.streams.keys().filter(|key| key.is_some()).count();
+        let oldest = session.streams.keys().find_map(|key| *key);
+        if let Some(oldest) =
+            oldest.filter(|_| request_wise_streams > self.max_request_wise_streams)
+        {
+            session.streams.remove(&Some(oldest));
+        }
+        std::future::ready(Ok(())).boxed()
+    }
+
+    fn replay(
+        &self,
+        stream: StreamKey,
+        from: usize,
+    ) -> BoxFuture<'_, Result<Vec<StoredEvent>, EventStoreError>> {
+        let events = self
+            .lock()
+            .get(&stream.session_id)
+            .and_then(|session| session.streams.get(&stream.http_request_id))
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|event| event.index >= from)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        std::future::ready(Ok(events)).boxed()
+    }
+
+    fn has_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<bool, EventStoreError>> {
+        let has_session = self.lock().contains_key(&session_id);
+        std::future::ready(Ok(has_session)).boxed()
+    }
+
+    fn remove_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<(), EventStoreError>> {
+        self.lock().remove(&session_id);
+        std::future::ready(Ok(())).boxed()
+    }
+}
+
+/// Append the events to files under a directory, one json line per event.
+///
+/// The events of a session are stored in `<root>/<session id>/`, in a file per stream. They are
+/// kept until the session is closed.
+#[derive(Debug)]
+pub struct FileEventStore {
+    root: PathBuf,
+    // the opened file of each stream, its lock serializes the appends to the stream so lines
+    // don't interleave, while the streams are written concurrently
+    streams: std::sync::Mutex<HashMap<StreamKey, StreamFile>>,
+}
+
+type StreamFile = Arc<tokio::sync::Mutex<Option<tokio::fs::File>>>;
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid session id for file event store: {0}")]
+pub struct InvalidSessionId(SessionId);
+
+impl FileEventStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            streams: Default::default(),
+        }
+    }
+    fn stream_file_lock(&self, stream: &StreamKey) -> StreamFile {
+        self.streams
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(stream.clone())
+            .or_default()
+            .clone()
+    }
+    fn session_dir(&self, session_id: &SessionId) -> Result<PathBuf, InvalidSessionId> {
+        // the session id comes from a request header, never let it escape the root directory
+        let valid = !session_id.is_empty()
+            && session_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if valid {
+            Ok(self.root.join(&**session_id))
+        } else {
+            Err(InvalidSessionId(session_id.clone()))
+        }
+    }
+    fn stream_file(&self, stream: &StreamKey) -> Result<PathBuf, InvalidSessionId> {
+        let file_name = match stream.http_request_id {
+            Some(http_request_id) => format!("{http_request_id}.jsonl"),
+            None => "common.jsonl".to_string(),
+        };
+        Ok(self.session_dir(&stream.session_id)?.join(file_name))
+    }
+}
+
+impl EventStore for FileEventStore {
+    fn append(
+        &self,
+        stream: StreamKey,
+        event: StoredEvent,
+    ) -> BoxFuture<'_, Result<(), EventStoreError>> {
+        async move {
+            let path = self.stream_file(&stream)?;
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            let stream_lock = self.stream_file_lock(&stream);
+            let mut opened = stream_lock.lock().await;
+            let mut file = match opened.take() {
+                Some(file) => file,
+                None => {
+                    if let Some(dir) = path.parent() {
+                        tokio::fs::create_dir_all(dir).await?;
+                    }
+                    tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&path)
+                        .await?
+                }
+            };
+            file.write_all(&line).await?;
+            // the file writes in the background, wait for the line to be written
+            file.flush().await?;
+            // a file that failed to write is dropped, and opened again by the next append
+            *opened = Some(file);
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn replay(
+        &self,
+        stream: StreamKey,
+        from: usize,
+    ) -> BoxFuture<'_, Result<Vec<StoredEvent>, EventStoreError>> {
+        async move {
+            let path = match self.stream_file(&stream) {
+                Ok(path) => path,
+                Err(_) => return Ok(Vec::new()),
+            };
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(e.into()),
+            };
+            let mut events = Vec::new();
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str::<StoredEvent>(line) {
+                    Ok(event) if event.index >= from => events.push(event),
+                    Ok(_) => {}
+                    // a line may be truncated if the process was killed while writing it
+                    Err(e) => tracing::warn!(?path, "skip invalid stored event: {e}"),
+                }
+            }
+            Ok(events)
+        }
+        .boxed()
+    }
+
+    fn has_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<bool, EventStoreError>> {
+        async move {
+            let Ok(dir) = self.session_dir(&session_id) else {
+                return Ok(false);
+            };
+            Ok(tokio::fs::try_exists(dir).await?)
+        }
+        .boxed()
+    }
+
+    fn remove_session(&self, session_id: SessionId) -> BoxFuture<'_, Result<(), EventStoreError>> {
+        async move {
+            let dir = self.session_dir(&session_id)?;
+            let stream_locks = {
+                let mut streams = self
+                    .streams
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let keys = streams
+                    .keys()
+                    .filter(|key| key.session_id == session_id)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                keys.into_iter()
+                    .filter_map(|key| streams.remove(&key))
+                    .collect::<Vec<_>>()
+            };
+            // wait for the pending appends, and close the files
+            for stream_lock in stream_locks {
+                stream_lock.lock().await.take();
+            }
+            match tokio::fs::remove_dir_all(dir).await {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e.into()),
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EmptyResult, NumberOrString, ServerResult};
+
+    fn event(index: usize) -> StoredEvent {
+        StoredEvent {
+            index,
+            message: Arc::new(ServerJsonRpcMessage::response(
+                ServerResult::EmptyResult(EmptyResult {}),
+                NumberOrString::Number(index as u32),
+            )),
+        }
+    }
+
+    fn indexes(events: &[StoredEvent]) -> Vec<usize> {
+        events.iter().map(|event| event.index).collect()
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_capacity() {
+        let store = InMemoryEventStore::new(3, 1);
+        let session_id: SessionId = "session".into();
+        let common = StreamKey::new(session_id.clone(), None);
+        for index in 0..5 {
+            store.append(common.clone(), event(index)).await.unwrap();
+        }
+        let events = store.replay(common.clone(), 0).await.unwrap();
+        assert_eq!(indexes(&events), vec![2, 3, 4]);
+        let events = store.replay(common.clone(), 4).await.unwrap();
+        assert_eq!(indexes(&events), vec![4]);
+
+        // only the latest request wise stream is kept
+        let first = StreamKey::new(session_id.clone(), Some(0));
+        let second = StreamKey::new(session_id.clone(), Some(1));
+        store.append(first.clone(), event(0)).await.unwrap();
+        store.append(second.clone(), event(0)).await.unwrap();
+        assert!(store.replay(first, 0).await.unwrap().is_empty());
+        assert_eq!(indexes(&store.replay(second, 0).await.unwrap()), vec![0]);
+        assert_eq!(store.replay(common, 0).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_reopen() {
+        let root = std::env::temp_dir().join(format!("rmcp-event-store-{}", uuid::Uuid::new_v4()));
+        let session_id: SessionId = "session-1".into();
+        let stream = StreamKey::new(session_id.clone(), Some(7));
+        {
+            let store = FileEventStore::new(&root);
+            for index in 0..3 {
+                store.append(stream.clone(), event(index)).await.unwrap();
+            }
+        }
+        let store = FileEventStore::new(&root);
+        assert!(store.has_session(session_id.clone()).await.unwrap());
+        let events = store.replay(stream.clone(), 1).await.unwrap();
+        assert_eq!(indexes(&events), vec![1, 2]);
+        assert_eq!(
+            serde_json::to_value(&events[0].message).unwrap(),
+            serde_json::to_value(&event(1).message).unwrap()
+        );
+
+        // path traversal is rejected
+        let escaped = StreamKey::new("../escaped".into(), None);
+        assert!(store.append(escaped.clone(), event(0)).await.is_err());
+        assert!(store.replay(escaped, 0).await.unwrap().is_empty());
+
+        store.remove_session(session_id.clone()).await.unwrap();
+        assert!(!store.has_session(session_id).await.unwrap());
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -165,12 +165,21 @@ where
                 .body(Full::new(Bytes::from("Unauthorized: Session ID is required")).boxed())
                 .expect("valid response"));
         };
-        // check if session exists
-        let has_session = self
-            .session_manager
-            .has_session(&session_id)
-            .await
-            .map_err(internal_error_response("check session"))?;
+        // check if last event id is provided
+        let last_event_id = request
+            .headers()
+            .get(HEADER_LAST_EVENT_ID)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_owned());
+        // check if session exists, a stored session can still be resumed
+        let has_session = if last_event_id.is_some() {
+            self.session_manager
+                .has_resumable_session(&session_id)
+                .await
+        } else {
+            self.session_manager.has_session(&session_id).await
+        }
+        .map_err(internal_error_response("check session"))?;
         if !has_session {
             // unauthorized
             return Ok(Response::builder()
@@ -178,12 +187,6 @@ where
                 .body(Full::new(Bytes::from("Unauthorized: Session not found")).boxed())
                 .expect("valid response"));
         }
-        // check if last event id is provided
-        let last_event_id = request
-            .headers()
-            .get(HEADER_LAST_EVENT_ID)
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_owned());
         if let Some(last_event_id) = last_event_id {
             // check if session has this event id
             let stream = self
@@ -1 +1,493 @@
-// Maybe we don't really need a ws implementation?
+//! WebSocket transport
+//!
+//! Each JSON-RPC message is carried in a single text frame. A background task owns the
+//! write half of the socket, it sends ping frames periodically and closes the connection
+//! when the peer stops answering.
+//!
+//! When the connection is closed, by the peer or because the keep-alive timed out, the
+//! close code is reported as a [`WsTransportError`] by every following send.
+//!
+//! # Example
+//! ```rust,no_run
+//! # use rmcp::{RoleClient, RoleServer, ServerHandler, ServiceExt, transport::ws};
+//! async fn client() -> Result<(), Box<dyn std::error::Error>> {
+//!     let transport = ws::connect::<RoleClient>("ws://127.0.0.1:8000/mcp").await?;
+//!     let client = ().serve(transport).await?;
+//!     let tools = client.peer().list_tools(Default::default()).await?;
+//!     println!("{:?}", tools);
+//!     Ok(())
+//! }
+//!
+//! async fn server(
+//!     handler: impl ServerHandler,
+//!     stream: tokio::net::TcpStream,
+//! ) -> Result<(), Box<dyn std::error::Error>> {
+//!     let transport = ws::accept::<RoleServer, _>(stream).await?;
+//!     let server = handler.serve(transport).await?;
+//!     server.waiting().await?;
+//!     Ok(())
+//! }
+//! ```
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{Sink, SinkExt, Stream, StreamExt, stream::SplitSink, stream::SplitStream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::Instant,
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream,
+    tungstenite::{
+        self, Message,
+        client::IntoClientRequest,
+        protocol::{CloseFrame, frame::coding::CloseCode},
+    },
+};
+use tokio_util::sync::{CancellationToken, PollSender, WaitForCancellationFutureOwned};
+
+use super::sink_stream::SinkStreamTransport;
+use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
+
+/// Close code sent when the connection was closed without a close frame
+const CLOSE_CODE_ABNORMAL: u16 = 1006;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WsTransportError {
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tungstenite::Error),
+    #[error("failed to serialize message: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("websocket closed with code {code}: {reason}")]
+    Closed { code: u16, reason: String },
+    #[error("no frame received from peer within {0:?}")]
+    KeepAliveTimeout(Duration),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WsTransportConfig {
+    /// Interval between two ping frames, `None` to disable the keep-alive
+    pub ping_interval: Option<Duration>,
+    /// How long to wait for any frame after a ping before closing the connection
+    pub pong_timeout: Duration,
+    /// Capacity of the outgoing message queue
+    pub channel_capacity: usize,
+}
+
+impl Default for WsTransportConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Some(Duration::from_secs(30)),
+            pong_timeout: Duration::from_secs(10),
+            channel_capacity: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CloseState {
+    Closed { code: u16, reason: String },
+    KeepAliveTimeout(Duration),
+}
+
+impl From<CloseState> for WsTransportError {
+    fn from(state: CloseState) -> Self {
+        match state {
+            CloseState::Closed { code, reason } => WsTransportError::Closed { code, reason },
+            CloseState::KeepAliveTimeout(timeout) => WsTransportError::KeepAliveTimeout(timeout),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    close_state: Mutex<Option<CloseState>>,
+    last_received: Mutex<Instant>,
+    /// Cancelled once the connection is closed, by either half
+    closed: CancellationToken,
+}
+
+impl Shared {
+    /// Record why the connection was closed, only the first reason is kept
+    fn set_closed(&self, state: CloseState) {
+        let mut close_state = self.close_state.lock().expect("ws state lock poisoned");
+        if close_state.is_none() {
+            *close_state = Some(state);
+        }
+        self.closed.cancel();
+    }
+    fn closed_error(&self) -> WsTransportError {
+        self.close_state
+            .lock()
+            .expect("ws state lock poisoned")
+            .clone()
+            .unwrap_or(CloseState::Closed {
+                code: CLOSE_CODE_ABNORMAL,
+                reason: "connection closed".to_string(),
+            })
+            .into()
+    }
+    fn touch(&self) {
+        *self.last_received.lock().expect("ws state lock poisoned") = Instant::now();
+    }
+    fn since_last_received(&self) -> Duration {
+        self.last_received
+            .lock()
+            .expect("ws state lock poisoned")
+            .elapsed()
+    }
+}
+
+/// The sending half of a websocket transport
+pub struct WsSink<Role> {
+    tx: PollSender<Message>,
+    shared: Arc<Shared>,
+    _marker: PhantomData<fn(Role)>,
+}
+
+impl<Role: ServiceRole> Sink<TxJsonRpcMessage<Role>> for WsSink<Role> {
+    type Error = WsTransportError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.shared.closed.is_cancelled() {
+            return Poll::Ready(Err(this.shared.closed_error()));
+        }
+        this.tx
+            .poll_ready_unpin(cx)
+            .map_err(|_| this.shared.closed_error())
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: TxJsonRpcMessage<Role>) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let text = serde_json::to_string(&item)?;
+        #[cfg(feature = "metrics")]
+        crate::metrics::Metrics::global().record_transport_bytes(
+            crate::metrics::Transport::WebSocket,
+            crate::transport::Direction::Sent,
+            text.len(),
+        );
+        this.tx
+            .start_send_unpin(Message::text(text))
+            .map_err(|_| this.shared.closed_error())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.tx
+            .poll_flush_unpin(cx)
+            .map_err(|_| this.shared.closed_error())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.tx
+            .poll_close_unpin(cx)
+            .map_err(|_| this.shared.closed_error())
+    }
+}
+
+/// The receiving half of a websocket transport
+pub struct WsStream<Role, S> {
+    stream: SplitStream<WebSocketStream<S>>,
+    shared: Arc<Shared>,
+    /// Ends the stream when the writer closes the connection, such as on a keep-alive timeout
+    closed: Pin<Box<WaitForCancellationFutureOwned>>,
+    _marker: PhantomData<fn() -> Role>,
+}
+
+impl<Role, S> Stream for WsStream<Role, S>
+where
+    Role: ServiceRole,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Item = RxJsonRpcMessage<Role>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.closed.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        loop {
+            let message = match this.stream.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(Ok(message))) => message,
+                Poll::Ready(Some(Err(e))) => {
+                    tracing::error!("websocket receive error: {e}");
+                    this.shared.set_closed(CloseState::Closed {
+                        code: CLOSE_CODE_ABNORMAL,
+                        reason: e.to_string(),
+                    });
+                    return Poll::Ready(None);
+                }
+            };
+            this.shared.touch();
+            #[cfg(feature = "metrics")]
+            if let Message::Text(_) | Message::Binary(_) = &message {
+                crate::metrics::Metrics::global().record_transport_bytes(
+                    crate::metrics::Transport::WebSocket,
+                    crate::transport::Direction::Received,
+                    message.len(),
+                );
+            }
+            let parsed = match message {
+                Message::Text(text) => serde_json::from_str(text.as_str()),
+                Message::Binary(bytes) => serde_json::from_slice(&bytes),
+                Message::Close(frame) => {
+                    let (code, reason) = match frame {
+                        Some(frame) => (u16::from(frame.code), frame.reason.to_string()),
+                        None => (u16::from(CloseCode::Status), String::new()),
+                    };
+                    tracing::debug!(code, %reason, "websocket closed by peer");
+                    this.shared.set_closed(CloseState::Closed { code, reason });
+                    return Poll::Ready(None);
+                }
+                // pongs are answered by tungstenite, any frame keeps the connection alive
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+            };
+            match parsed {
+                Ok(message) => return Poll::Ready(Some(message)),
+                Err(e) => {
+                    tracing::warn!("failed to deserialize websocket message: {e}");
+                    continue;
+                }
+            }
+        }
+    }
+}
+
+/// A transport over a websocket connection, see the [module level documentation](self)
+pub type WsTransport<Role, S> = SinkStreamTransport<WsSink<Role>, WsStream<Role, S>>;
+
+async fn write_loop<S>(
+    mut sink: SplitSink<WebSocketStream<S>, Message>,
+    mut rx: tokio::sync::mpsc::Receiver<Message>,
+    shared: Arc<Shared>,
+    config: WsTransportConfig,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut ping = config.ping_interval.map(|period| {
+        (
+            period,
+            tokio::time::interval_at(Instant::now() + period, period),
+        )
+    });
+    loop {
+        let tick = async {
+            match ping.as_mut() {
+                Some((period, interval)) => {
+                    interval.tick().await;
+                    *period
+                }
+                None => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            _ = shared.closed.cancelled() => break,
+            message = rx.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+                sink.send(message).await
+            }
+            period = tick => {
+                if shared.since_last_received() > period + config.pong_timeout {
+                    tracing::warn!("websocket keep-alive timeout");
+                    shared.set_closed(CloseState::KeepAliveTimeout(period + config.pong_timeout));
+                    let _ = sink
+                        .send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Away,
+                            reason: "keep-alive timeout".into(),
+                        })))
+                        .await;
+                    return;
+                }
+                sink.send(Message::Ping(Default::default())).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!("websocket send error: {e}");
+            shared.set_closed(CloseState::Closed {
+                code: CLOSE_CODE_ABNORMAL,
+                reason: e.to_string(),
+            });
+            return;
+        }
+    }
+    // all senders were dropped or the peer closed the connection, close it gracefully
+    if let Err(e) = sink.close().await {
+        tracing::debug!("websocket close error: {e}");
+    }
+}
+
+/// Create a transport from an established websocket connection.
+///
+/// This spawns the task writing to the socket, so it must be called inside a tokio runtime.
+pub fn from_websocket<Role, S>(
+    websocket: WebSocketStream<S>,
+    config: WsTransportConfig,
+) -> WsTransport<Role, S>
+where
+    Role: ServiceRole,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sink, stream) = websocket.split();
+    let shared = Arc::new(Shared {
+        close_state: Mutex::new(None),
+        last_received: Mutex::new(Instant::now()),
+        closed: CancellationToken::new(),
+    });
+    let (tx, rx) = tokio::sync::mpsc::channel(config.channel_capacity);
+    tokio::spawn(write_loop(sink, rx, shared.clone(), config));
+    SinkStreamTransport::new(
+        WsSink {
+            tx: PollSender::new(tx),
+            shared: shared.clone(),
+            _marker: PhantomData,
+        },
+        WsStream {
+            stream,
+            closed: Box::pin(shared.closed.clone().cancelled_owned()),
+            shared,
+            _marker: PhantomData,
+        },
+    )
+}
+
+/// Connect to a websocket server with the default config
+pub async fn connect<Role: ServiceRole>(
+    request: impl IntoClientRequest + Unpin,
+) -> Result<WsTransport<Role, MaybeTlsStream<tokio::net::TcpStream>>, WsTransportError> {
+    connect_with_config(request, WsTransportConfig::default()).await
+}
+
+pub async fn connect_with_config<Role: ServiceRole>(
+    request: impl IntoClientRequest + Unpin,
+    config: WsTransportConfig,
+) -> Result<WsTransport<Role, MaybeTlsStream<tokio::net::TcpStream>>, WsTransportError> {
+    let (websocket, _response) = tokio_tungstenite::connect_async(request).await?;
+    Ok(from_websocket(websocket, config))
+}
+
+/// Accept a websocket connection on an incoming stream with the default config
+pub async fn accept<Role, S>(stream: S) -> Result<WsTransport<Role, S>, WsTransportError>
+where
+    Role: ServiceRole,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    accept_with_config(stream, WsTransportConfig::default()).await
+}
+
+pub async fn accept_with_config<Role, S>(
+    stream: S,
+    config: WsTransportConfig,
+) -> Result<WsTransport<Role, S>, WsTransportError>
+where
+    Role: ServiceRole,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let websocket = tokio_tungstenite::accept_async(stream).await?;
+    Ok(from_websocket(websocket, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        RoleClient, RoleServer,
+        model::{ClientJsonRpcMessage, ClientRequest, PingRequest, RequestId},
+        transport::Transport,
+    };
+
+    async fn pair(
+        config: WsTransportConfig,
+    ) -> (
+        WsTransport<RoleClient, tokio::io::DuplexStream>,
+        WebSocketStream<tokio::io::DuplexStream>,
+    ) {
+        let (client, server) = tokio::io::duplex(4096);
+        let (client, server) = tokio::join!(
+            tokio_tungstenite::client_async("ws://localhost/mcp", client),
+            tokio_tungstenite::accept_async(server)
+        );
+        let (client, _response) = client.expect("client handshake");
+        let server = server.expect("server handshake");
+        (from_websocket(client, config), server)
+    }
+
+    fn ping() -> ClientJsonRpcMessage {
+        ClientJsonRpcMessage::request(
+            ClientRequest::PingRequest(PingRequest::default()),
+            RequestId::Number(1),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_text_frame_round_trip() {
+        let (mut client, server) = pair(WsTransportConfig::default()).await;
+        let mut server = from_websocket::<RoleServer, _>(server, WsTransportConfig::default());
+        Transport::<RoleClient>::send(&mut client, ping())
+            .await
+            .expect("send");
+        let received = Transport::<RoleServer>::receive(&mut server)
+            .await
+            .expect("receive");
+        assert!(matches!(
+            received,
+            ClientJsonRpcMessage::Request(ref request) if request.id == RequestId::Number(1)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_close_code_is_propagated() {
+        let (mut client, mut server) = pair(WsTransportConfig::default()).await;
+        server
+            .close(Some(CloseFrame {
+                code: CloseCode::Policy,
+                reason: "bye".into(),
+            }))
+            .await
+            .expect("close");
+        assert!(
+            Transport::<RoleClient>::receive(&mut client)
+                .await
+                .is_none()
+        );
+        // the close is seen by the sender as soon as the receiver has seen it
+        match Transport::<RoleClient>::send(&mut client, ping()).await {
+            Err(WsTransportError::Closed { code, reason }) => {
+                assert_eq!(code, 1008);
+                assert_eq!(reason, "bye");
+            }
+            other => panic!("expected close error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_timeout_ends_receive() {
+        let config = WsTransportConfig {
+            ping_interval: Some(Duration::from_secs(1)),
+            pong_timeout: Duration::from_secs(1),
+            ..Default::default()
+        };
+        // the server never reads, so it never answers the pings
+        let (mut client, _server) = pair(config).await;
+        tokio::time::pause();
+        assert!(
+            Transport::<RoleClient>::receive(&mut client)
+                .await
+                .is_none()
+        );
+        assert!(matches!(
+            Transport::<RoleClient>::send(&mut client, ping()).await,
+            Err(WsTransportError::KeepAliveTimeout(timeout)) if timeout == Duration::from_secs(2)
+        ));
+    }
+}